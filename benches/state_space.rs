@@ -1,8 +1,18 @@
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+};
+
+use alloy::primitives::{Address, B256};
 use amms::{
-    amm::{uniswap_v2::UniswapV2Pool, AMM},
-    state_space::{cache::StateChangeCache, StateChange},
+    amm::{uniswap_v2::UniswapV2Pool, AutomatedMarketMaker, AMM},
+    state_space::{
+        cache::StateChangeCache,
+        store::{LockedStateSpace, ShardedStateSpace, StateSpaceStore},
+        StateChange,
+    },
 };
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
 
 pub fn add_state_changes_benchmark(c: &mut Criterion) {
     let state_changes: Vec<StateChange> = (0..150)
@@ -11,6 +21,7 @@ pub fn add_state_changes_benchmark(c: &mut Criterion) {
 
             StateChange {
                 block_number: i,
+                block_hash: B256::ZERO,
                 state_change: amms,
             }
         })
@@ -19,7 +30,7 @@ pub fn add_state_changes_benchmark(c: &mut Criterion) {
     // Benchmark adding state changes to the cache with setup
     c.bench_function("add state changes to cache with setup", |b| {
         b.iter_batched(
-            || StateChangeCache::new(),
+            StateChangeCache::new,
             |mut cache| {
                 for state_change in state_changes.clone() {
                     cache
@@ -32,5 +43,75 @@ pub fn add_state_changes_benchmark(c: &mut Criterion) {
     });
 }
 
-criterion_group!(benches, add_state_changes_benchmark);
+fn amms(count: u64) -> Vec<AMM> {
+    (0..count)
+        .map(|i| {
+            AMM::UniswapV2Pool(UniswapV2Pool {
+                address: Address::left_padding_from(&i.to_be_bytes()),
+                ..Default::default()
+            })
+        })
+        .collect()
+}
+
+/// Applies an update to each AMM in the store while `readers` threads continuously read from it
+fn apply_block_with_readers<S: StateSpaceStore>(store: &S, addresses: &[Address], readers: usize) {
+    let done = AtomicBool::new(false);
+
+    thread::scope(|scope| {
+        for reader in 0..readers {
+            let done = &done;
+            scope.spawn(move || {
+                let mut i = reader;
+                while !done.load(Ordering::Relaxed) {
+                    black_box(store.get(&addresses[i % addresses.len()]));
+                    i += 1;
+                }
+            });
+        }
+
+        for address in addresses {
+            store.update(address, |amm| {
+                if let AMM::UniswapV2Pool(pool) = amm {
+                    pool.reserve_0 += 1;
+                }
+            });
+        }
+
+        done.store(true, Ordering::Relaxed);
+    });
+}
+
+pub fn state_space_contention_benchmark(c: &mut Criterion) {
+    let amms = amms(10_000);
+    let addresses = amms
+        .iter()
+        .map(|amm| amm.address())
+        .collect::<Vec<Address>>();
+
+    let locked = LockedStateSpace::from(amms.clone());
+    let sharded = ShardedStateSpace::from(amms);
+
+    let mut group = c.benchmark_group("apply block with concurrent readers");
+    for readers in [0, 2, 8] {
+        group.bench_with_input(
+            BenchmarkId::new("locked", readers),
+            &readers,
+            |b, &readers| b.iter(|| apply_block_with_readers(&locked, &addresses, readers)),
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("sharded", readers),
+            &readers,
+            |b, &readers| b.iter(|| apply_block_with_readers(&sharded, &addresses, readers)),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    add_state_changes_benchmark,
+    state_space_contention_benchmark
+);
 criterion_main!(benches);
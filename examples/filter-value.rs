@@ -18,7 +18,7 @@ async fn main() -> eyre::Result<()> {
     tracing_subscriber::fmt::init();
 
     let rpc_endpoint = std::env::var("ETHEREUM_RPC_ENDPOINT")?;
-    let provider = Arc::new(ProviderBuilder::new().on_http(rpc_endpoint.parse()?));
+    let provider = Arc::new(ProviderBuilder::new().connect_http(rpc_endpoint.parse()?));
 
     // Initialize factories
     let factories = vec![
@@ -7,6 +7,7 @@ use amms::{
     state_space::StateSpaceManager,
     sync,
 };
+use futures::StreamExt;
 
 #[tokio::main]
 async fn main() -> eyre::Result<()> {
@@ -16,7 +17,7 @@ async fn main() -> eyre::Result<()> {
 
     // Initialize WS provider
     let ws = WsConnect::new(ws_endpoint);
-    let provider = Arc::new(ProviderBuilder::new().on_ws(ws).await?);
+    let provider = Arc::new(ProviderBuilder::new().connect_ws(ws).await?);
 
     // Initialize factories
     let factories = vec![
@@ -38,7 +39,7 @@ async fn main() -> eyre::Result<()> {
 
     // Sync amms
     let (mut amms, last_synced_block) =
-        sync::sync_amms(factories, provider.clone(), None, step).await?;
+        sync::sync_amms(factories.clone(), provider.clone(), None, step).await?;
 
     // Discover vaults and add them to amms
     let vaults = discovery::erc_4626::discover_erc_4626_vaults(provider.clone(), step)
@@ -52,15 +53,17 @@ async fn main() -> eyre::Result<()> {
     // Initialize state space manager
     let state_space_manager = StateSpaceManager::new(amms, provider);
 
-    //Listen for state changes and print them out
-    let (mut rx, _join_handles) = state_space_manager
-        .subscribe_state_changes(last_synced_block, 100)
+    // Add pools created by the factories to the state space as they are discovered
+    state_space_manager.set_factories(factories).await;
+
+    // Listen for state changes and print them out
+    let stream = state_space_manager
+        .state_change_stream(last_synced_block, 100)
         .await?;
+    let mut stream = Box::pin(stream.take(10));
 
-    for _ in 0..10 {
-        if let Some(state_changes) = rx.recv().await {
-            println!("State changes: {:?}", state_changes);
-        }
+    while let Some(state_changes) = stream.next().await {
+        println!("State changes: {:?}", state_changes);
     }
 
     Ok(())
@@ -9,7 +9,10 @@ use alloy::{
 };
 
 use super::ERC4626Vault;
-use crate::{amm::AutomatedMarketMaker, errors::AMMError};
+use crate::{
+    amm::{AutomatedMarketMaker, AMM},
+    errors::AMMError,
+};
 
 sol! {
     #[allow(missing_docs)]
@@ -65,8 +68,93 @@ fn populate_pool_data_from_tokens(
     Some(vault)
 }
 
+/// Populates the vaults at the block number via a batch request.
+///
+/// Vaults that could not be read or charge fees that are not relative are reset to an empty vault with only the vault token set,
+/// so that stale data is never served and the vaults are removed by `filters::filter_empty_amms`.
+pub async fn get_amm_data_batch_request<N, P>(
+    amms: &mut [AMM],
+    block_number: Option<u64>,
+    provider: Arc<P>,
+) -> Result<(), AMMError>
+where
+    N: Network,
+    P: Provider<N>,
+{
+    let mut target_addresses = vec![];
+    for amm in amms.iter() {
+        target_addresses.push(amm.address());
+    }
+
+    let deployer = IGetERC4626VaultDataBatchRequest::deploy_builder(provider, target_addresses);
+    let res = if let Some(block_number) = block_number {
+        deployer.block(block_number.into()).call_raw().await?
+    } else {
+        deployer.call_raw().await?
+    };
+
+    let constructor_return = DynSolType::Array(Box::new(DynSolType::Tuple(vec![
+        DynSolType::Address,
+        DynSolType::Uint(8),
+        DynSolType::Address,
+        DynSolType::Uint(8),
+        DynSolType::Uint(256),
+        DynSolType::Uint(256),
+        DynSolType::Uint(256),
+        DynSolType::Uint(256),
+        DynSolType::Uint(256),
+        DynSolType::Uint(256),
+        DynSolType::Uint(256),
+        DynSolType::Uint(256),
+    ])));
+    let return_data_tokens = constructor_return.abi_decode_sequence(&res)?;
+
+    let vaults_data = return_data_tokens.as_array().unwrap_or_default();
+
+    // Every vault must be returned, otherwise the remaining vaults would keep their previous data
+    if let Some(amm) = amms.get(vaults_data.len()) {
+        return Err(AMMError::BatchRequestError(amm.address()));
+    }
+
+    for (amm, vault_data) in amms.iter_mut().zip(vaults_data) {
+        let AMM::ERC4626Vault(erc_4626_vault) = amm else {
+            return Err(AMMError::IncongruentAMMs);
+        };
+
+        let vault_data = vault_data
+            .as_tuple()
+            .ok_or(AMMError::BatchRequestError(erc_4626_vault.address()))?;
+
+        // A zero vault token signals that the vault data could not be read
+        let vault = vault_data[0]
+            .as_address()
+            .filter(|address| !address.is_zero())
+            .and_then(|_| populate_pool_data_from_tokens(erc_4626_vault.to_owned(), vault_data));
+
+        match vault {
+            Some(vault) => {
+                tracing::trace!(?vault);
+                *erc_4626_vault = vault;
+            }
+            None => {
+                tracing::debug!(
+                    vault = ?erc_4626_vault.address(),
+                    "ignoring vault that could not be populated"
+                );
+                *erc_4626_vault = ERC4626Vault {
+                    vault_token: erc_4626_vault.vault_token,
+                    ..Default::default()
+                };
+            }
+        }
+    }
+
+    Ok(())
+}
+
 pub async fn get_4626_vault_data_batch_request<N, P>(
     vault: &mut ERC4626Vault,
+    block_number: Option<u64>,
     provider: Arc<P>,
 ) -> Result<(), AMMError>
 where
@@ -75,7 +163,11 @@ where
 {
     let deployer =
         IGetERC4626VaultDataBatchRequest::deploy_builder(provider, vec![vault.vault_token]);
-    let res = deployer.call_raw().await?;
+    let res = if let Some(block_number) = block_number {
+        deployer.block(block_number.into()).call_raw().await?
+    } else {
+        deployer.call_raw().await?
+    };
 
     let constructor_return = DynSolType::Array(Box::new(DynSolType::Tuple(vec![
         DynSolType::Address,
@@ -33,7 +33,7 @@ sol! {
     }
 }
 
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
 pub struct ERC4626Vault {
     /// token received from depositing, i.e. shares token
     pub vault_token: Address,
@@ -110,14 +110,15 @@ impl AutomatedMarketMaker for ERC4626Vault {
     #[instrument(skip(self, provider), level = "debug")]
     async fn populate_data<N, P>(
         &mut self,
-        _block_number: Option<u64>,
+        block_number: Option<u64>,
         provider: Arc<P>,
     ) -> Result<(), AMMError>
     where
         N: Network,
         P: Provider<N>,
     {
-        batch_request::get_4626_vault_data_batch_request(self, provider.clone()).await?;
+        batch_request::get_4626_vault_data_batch_request(self, block_number, provider.clone())
+            .await?;
 
         Ok(())
     }
@@ -313,7 +314,7 @@ mod tests {
     #[tokio::test]
     async fn test_get_vault_data() {
         let rpc_endpoint = std::env::var("ETHEREUM_RPC_ENDPOINT").unwrap();
-        let provider = Arc::new(ProviderBuilder::new().on_http(rpc_endpoint.parse().unwrap()));
+        let provider = Arc::new(ProviderBuilder::new().connect_http(rpc_endpoint.parse().unwrap()));
 
         let mut vault = ERC4626Vault {
             vault_token: address!("163538E22F4d38c1eb21B79939f3d2ee274198Ff"),
@@ -335,7 +336,7 @@ mod tests {
     #[tokio::test]
     async fn test_calculate_price_varying_decimals() {
         let rpc_endpoint = std::env::var("ETHEREUM_RPC_ENDPOINT").unwrap();
-        let provider = Arc::new(ProviderBuilder::new().on_http(rpc_endpoint.parse().unwrap()));
+        let provider = Arc::new(ProviderBuilder::new().connect_http(rpc_endpoint.parse().unwrap()));
 
         let mut vault = ERC4626Vault {
             vault_token: address!("163538E22F4d38c1eb21B79939f3d2ee274198Ff"),
@@ -358,7 +359,7 @@ mod tests {
     #[tokio::test]
     async fn test_calculate_price_zero_reserve() {
         let rpc_endpoint = std::env::var("ETHEREUM_RPC_ENDPOINT").unwrap();
-        let provider = Arc::new(ProviderBuilder::new().on_http(rpc_endpoint.parse().unwrap()));
+        let provider = Arc::new(ProviderBuilder::new().connect_http(rpc_endpoint.parse().unwrap()));
 
         let mut vault = ERC4626Vault {
             vault_token: address!("163538E22F4d38c1eb21B79939f3d2ee274198Ff"),
@@ -380,7 +381,7 @@ mod tests {
     #[tokio::test]
     async fn test_calculate_price() {
         let rpc_endpoint = std::env::var("ETHEREUM_RPC_ENDPOINT").unwrap();
-        let provider = Arc::new(ProviderBuilder::new().on_http(rpc_endpoint.parse().unwrap()));
+        let provider = Arc::new(ProviderBuilder::new().connect_http(rpc_endpoint.parse().unwrap()));
 
         let mut vault = ERC4626Vault {
             vault_token: address!("163538E22F4d38c1eb21B79939f3d2ee274198Ff"),
@@ -402,7 +403,7 @@ mod tests {
     #[tokio::test]
     async fn test_calculate_price_64_x_64() {
         let rpc_endpoint = std::env::var("ETHEREUM_RPC_ENDPOINT").unwrap();
-        let provider = Arc::new(ProviderBuilder::new().on_http(rpc_endpoint.parse().unwrap()));
+        let provider = Arc::new(ProviderBuilder::new().connect_http(rpc_endpoint.parse().unwrap()));
 
         let mut vault = ERC4626Vault {
             vault_token: address!("163538E22F4d38c1eb21B79939f3d2ee274198Ff"),
@@ -424,7 +425,7 @@ mod tests {
     #[tokio::test]
     async fn test_simulate_swap() {
         let rpc_endpoint = std::env::var("ETHEREUM_RPC_ENDPOINT").unwrap();
-        let provider = Arc::new(ProviderBuilder::new().on_http(rpc_endpoint.parse().unwrap()));
+        let provider = Arc::new(ProviderBuilder::new().connect_http(rpc_endpoint.parse().unwrap()));
 
         let mut vault = ERC4626Vault {
             vault_token: address!("163538E22F4d38c1eb21B79939f3d2ee274198Ff"),
@@ -225,7 +225,7 @@ impl TryFrom<B256> for Factory {
         } else if value == IUniswapV3Factory::PoolCreated::SIGNATURE_HASH {
             Ok(Factory::UniswapV3Factory(UniswapV3Factory::default()))
         } else {
-            return Err(EventLogError::InvalidEventSignature);
+            Err(EventLogError::InvalidEventSignature)
         }
     }
 }
@@ -75,6 +75,7 @@ where
 
 pub async fn get_amm_data_batch_request<N, P>(
     amms: &mut [AMM],
+    block_number: Option<u64>,
     provider: Arc<P>,
 ) -> Result<(), AMMError>
 where
@@ -87,7 +88,11 @@ where
     }
 
     let deployer = IGetUniswapV2PoolDataBatchRequest::deploy_builder(provider, target_addresses);
-    let res = deployer.call().await?;
+    let res = if let Some(block_number) = block_number {
+        deployer.block(block_number.into()).call().await?
+    } else {
+        deployer.call().await?
+    };
 
     let constructor_return = DynSolType::Array(Box::new(DynSolType::Tuple(vec![
         DynSolType::Address,
@@ -132,6 +137,7 @@ where
 
 pub async fn get_v2_pool_data_batch_request<N, P>(
     pool: &mut UniswapV2Pool,
+    block_number: Option<u64>,
     provider: Arc<P>,
 ) -> Result<(), AMMError>
 where
@@ -139,7 +145,11 @@ where
     P: Provider<N>,
 {
     let deployer = IGetUniswapV2PoolDataBatchRequest::deploy_builder(provider, vec![pool.address]);
-    let res = deployer.call_raw().await?;
+    let res = if let Some(block_number) = block_number {
+        deployer.block(block_number.into()).call_raw().await?
+    } else {
+        deployer.call_raw().await?
+    };
 
     let constructor_return = DynSolType::Array(Box::new(DynSolType::Tuple(vec![
         DynSolType::Address,
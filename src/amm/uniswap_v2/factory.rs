@@ -14,7 +14,7 @@ use tracing::instrument;
 
 use super::{batch_request, UniswapV2Pool, U256_1};
 use crate::{
-    amm::{factory::AutomatedMarketMakerFactory, AMM},
+    amm::{factory::AutomatedMarketMakerFactory, AutomatedMarketMaker, AMM},
     errors::AMMError,
 };
 
@@ -119,10 +119,22 @@ impl AutomatedMarketMakerFactory for UniswapV2Factory {
         N: Network,
         P: Provider<N>,
     {
+        let block_number = log.block_number.ok_or(AMMError::BlockNumberNotFound)?;
         let pair_created_event = IUniswapV2Factory::PairCreated::decode_log(log.as_ref())?;
-        Ok(AMM::UniswapV2Pool(
-            UniswapV2Pool::new_from_address(pair_created_event.pair, self.fee, provider).await?,
-        ))
+
+        // Populate the pool as of the end of its creation block, so that only later logs need to be applied
+        let mut pool = UniswapV2Pool {
+            address: pair_created_event.pair,
+            fee: self.fee,
+            ..Default::default()
+        };
+        pool.populate_data(Some(block_number), provider).await?;
+
+        if !pool.data_is_populated() {
+            return Err(AMMError::PoolDataError);
+        }
+
+        Ok(AMM::UniswapV2Pool(pool))
     }
 
     fn new_empty_amm_from_log(&self, log: Log) -> Result<AMM, alloy::sol_types::Error> {
@@ -157,7 +169,7 @@ impl AutomatedMarketMakerFactory for UniswapV2Factory {
     async fn populate_amm_data<N, P>(
         &self,
         amms: &mut [AMM],
-        _block_number: Option<u64>,
+        block_number: Option<u64>,
         middleware: Arc<P>,
     ) -> Result<(), AMMError>
     where
@@ -167,7 +179,8 @@ impl AutomatedMarketMakerFactory for UniswapV2Factory {
         // Max batch size for call
         let step = 127;
         for amm_chunk in amms.chunks_mut(step) {
-            batch_request::get_amm_data_batch_request(amm_chunk, middleware.clone()).await?;
+            batch_request::get_amm_data_batch_request(amm_chunk, block_number, middleware.clone())
+                .await?;
         }
         Ok(())
     }
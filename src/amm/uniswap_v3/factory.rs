@@ -60,11 +60,20 @@ impl AutomatedMarketMakerFactory for UniswapV3Factory {
         P: Provider<N>,
     {
         if let Some(block_number) = log.block_number {
-            let pool_created_filter = IUniswapV3Factory::PoolCreated::decode_log(&log.inner)?;
-            Ok(AMM::UniswapV3Pool(
-                UniswapV3Pool::new_from_address(pool_created_filter.pool, block_number, provider)
-                    .await?,
-            ))
+            let mut new_pool = self.new_empty_amm_from_log(log)?;
+
+            // Populate the pool as of the end of its creation block, so that only later logs need to be applied
+            if let AMM::UniswapV3Pool(ref mut pool) = new_pool {
+                pool.populate_tick_data_in_range(block_number, block_number, provider.clone())
+                    .await?;
+                pool.populate_data(Some(block_number), provider).await?;
+
+                if !pool.data_is_populated() {
+                    return Err(AMMError::PoolDataError);
+                }
+            }
+
+            Ok(new_pool)
         } else {
             return Err(AMMError::BlockNumberNotFound);
         }
@@ -57,7 +57,7 @@ sol! {
 
 pub const ONE: U256 = uint!(1_U256);
 
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
 pub struct UniswapV3Pool {
     pub address: Address,
     pub token_a: Address,
@@ -74,7 +74,7 @@ pub struct UniswapV3Pool {
     #[serde(skip)]
     pub positions: HashMap<u64, Position>,
 }
-#[derive(Debug, Clone, Default, Copy)]
+#[derive(Debug, Clone, PartialEq, Default, Copy)]
 pub struct Position {
     tick_lower: i32,
     tick_upper: i32,
@@ -83,7 +83,7 @@ pub struct Position {
     fee1: U256,
 }
 
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
 pub struct Info {
     pub liquidity_gross: u128,
     pub liquidity_net: i128,
@@ -348,7 +348,7 @@ impl UniswapV3Pool {
     /// Returns the last synced block number.
     pub async fn populate_tick_data<N, P>(
         &mut self,
-        mut from_block: u64,
+        from_block: u64,
         provider: Arc<P>,
     ) -> Result<u64, AMMError>
     where
@@ -360,18 +360,35 @@ impl UniswapV3Pool {
             .await
             .map_err(AMMError::TransportError)?;
 
+        self.populate_tick_data_in_range(from_block, current_block, provider)
+            .await?;
+
+        Ok(current_block)
+    }
+
+    /// Populates the `tick_bitmap` and `ticks` fields of the pool from the mint and burn logs between `from_block` and `to_block`.
+    pub async fn populate_tick_data_in_range<N, P>(
+        &mut self,
+        mut from_block: u64,
+        to_block: u64,
+        provider: Arc<P>,
+    ) -> Result<(), AMMError>
+    where
+        N: Network,
+        P: Provider<N>,
+    {
         let mut futures = FuturesOrdered::new();
 
         let mut ordered_logs: BTreeMap<u64, Vec<Log>> = BTreeMap::new();
 
         let pool_address: Address = self.address;
 
-        while from_block <= current_block {
+        while from_block <= to_block {
             let middleware = provider.clone();
 
             let mut target_block = from_block + POPULATE_TICK_DATA_STEP - 1;
-            if target_block > current_block {
-                target_block = current_block;
+            if target_block > to_block {
+                target_block = to_block;
             }
 
             futures.push_back(async move {
@@ -415,7 +432,7 @@ impl UniswapV3Pool {
             }
         }
 
-        Ok(current_block)
+        Ok(())
     }
 
     pub fn simulate_swap_with_limit(
@@ -657,27 +674,27 @@ impl UniswapV3Pool {
                 .0;
 
             current_state.amount_calculated -= I256::from_raw(step.amount_out);
-            for (_, position) in &mut self.positions {
+            for position in self.positions.values_mut() {
                 if self.liquidity > 0
                     && current_state.tick >= position.tick_lower
                     && current_state.tick < position.tick_upper
                 {
                     log::trace!("step.fee_amount: {}, current_state.liquidity {}, zero_for_one {zero_for_one}, position.liquidity: {}", step.fee_amount, current_state.liquidity, position.liquidity);
                     if zero_for_one {
-                        position.fee0 += U256::uint_try_from(
+                        position.fee0 += (U256::uint_try_from(
                             (U512::from(step.fee_amount) << 128)
                                 / (U512::from(current_state.liquidity)),
                         )
                         .expect("Failed to cast U512 to U256")
-                            * U256::from(position.liquidity)
+                            * U256::from(position.liquidity))
                             >> 128;
                     } else {
-                        position.fee1 += U256::uint_try_from(
+                        position.fee1 += (U256::uint_try_from(
                             (U512::from(step.fee_amount) << 128)
                                 / (U512::from(current_state.liquidity)),
                         )
                         .expect("Failed to cast U512 to U256")
-                            * U256::from(position.liquidity)
+                            * U256::from(position.liquidity))
                             >> 128;
                     }
                 }
@@ -921,6 +938,32 @@ impl UniswapV3Pool {
         Ok(())
     }
 
+    /// Updates the `tick_bitmap` and `ticks` of the pool from a mint or burn event log, ignoring any other event log.
+    ///
+    /// Unlike `sync_from_log`, the liquidity, price and tick are left untouched, so the ticks of a pool can be rebuilt
+    /// from historical logs after its data has been populated.
+    pub fn sync_ticks_from_log(&mut self, log: Log) -> Result<(), EventLogError> {
+        let event_signature = log.topics()[0];
+
+        if event_signature == IUniswapV3Pool::Burn::SIGNATURE_HASH {
+            let burn_event = IUniswapV3Pool::Burn::decode_log(log.as_ref())?;
+            self.update_position(
+                burn_event.tickLower.as_i32(),
+                burn_event.tickUpper.as_i32(),
+                -(burn_event.amount as i128),
+            );
+        } else if event_signature == IUniswapV3Pool::Mint::SIGNATURE_HASH {
+            let mint_event = IUniswapV3Pool::Mint::decode_log(log.as_ref())?;
+            self.update_position(
+                mint_event.tickLower.as_i32(),
+                mint_event.tickUpper.as_i32(),
+                mint_event.amount as i128,
+            );
+        }
+
+        Ok(())
+    }
+
     pub fn mint_helper(
         &self,
         amount0: U256,
@@ -1143,7 +1186,7 @@ impl UniswapV3Pool {
         }
         let numerator1 = U512::from(liquidity) << 96;
         let numerator2 = U512::from(b - a);
-        let amount0 = if roundup {
+        if roundup {
             let mut result = U256::uint_try_from((numerator1 * numerator2) / U512::from(b))
                 .expect("Failed to convert U512 to U256");
             if (numerator1 * numerator2) % U512::from(b) > U512::ZERO {
@@ -1162,8 +1205,7 @@ impl UniswapV3Pool {
                     .expect("Failed to convert U512 to U256"),
             )
             .expect("Failed to convert U256 to I256")
-        };
-        return amount0;
+        }
     }
 
     fn get_amount_0_delta_inverted(
@@ -1197,7 +1239,7 @@ impl UniswapV3Pool {
             (a, b) = (b, a);
         }
 
-        let amount1 = if roundup {
+        if roundup {
             let mut result = U256::uint_try_from((U512::from(liquidity) * U512::from(b - a)) >> 96)
                 .expect("Failed to convert U512 to U256");
             if (U512::from(liquidity) * U512::from(b - a))
@@ -1213,8 +1255,7 @@ impl UniswapV3Pool {
                     .expect("Failed to convert U512 to U256"),
             )
             .expect("Failed to convert U256 to I256")
-        };
-        return amount1;
+        }
     }
 
     fn get_amount_1_delta_inverted(
@@ -1284,7 +1325,7 @@ impl UniswapV3Pool {
                 )
             }
         }
-        return (amount0, amount1);
+        (amount0, amount1)
     }
 
     pub fn update_position(&mut self, tick_lower: i32, tick_upper: i32, liquidity_delta: i128) {
@@ -1540,7 +1581,7 @@ pub struct Tick {
 mod test {
 
     use alloy::{
-        primitives::{address, U256},
+        primitives::{address, aliases::U24, U160, U256},
         providers::ProviderBuilder,
     };
 
@@ -1599,7 +1640,7 @@ mod test {
     #[ignore] // Ignoring to not throttle the Provider on workflows
     async fn test_simulate_swap_usdc_weth() {
         let rpc_endpoint = std::env::var("ETHEREUM_RPC_ENDPOINT").unwrap();
-        let provider = Arc::new(ProviderBuilder::new().on_http(rpc_endpoint.parse().unwrap()));
+        let provider = Arc::new(ProviderBuilder::new().connect_http(rpc_endpoint.parse().unwrap()));
 
         let (pool, synced_block) = initialize_usdc_weth_pool(provider.clone()).await.unwrap();
         let quoter = IQuoter::new(
@@ -1610,13 +1651,19 @@ mod test {
         let amount_in = U256::from(100000000); // 100 USDC
         let amount_out = pool.simulate_swap(pool.token_a, amount_in).unwrap();
         let expected_amount_out = quoter
-            .quoteExactInputSingle(pool.token_a, pool.token_b, pool.fee, amount_in, U256::ZERO)
+            .quoteExactInputSingle(
+                pool.token_a,
+                pool.token_b,
+                U24::from(pool.fee),
+                amount_in,
+                U160::ZERO,
+            )
             .block(synced_block.into())
             .call()
             .await
             .unwrap();
 
-        assert_eq!(amount_out, expected_amount_out.amountOut);
+        assert_eq!(amount_out, expected_amount_out);
 
         let amount_in_1 = U256::from(10000000000_u64); // 10_000 USDC
         let amount_out_1 = pool.simulate_swap(pool.token_a, amount_in_1).unwrap();
@@ -1624,16 +1671,16 @@ mod test {
             .quoteExactInputSingle(
                 pool.token_a,
                 pool.token_b,
-                pool.fee,
+                U24::from(pool.fee),
                 amount_in_1,
-                U256::ZERO,
+                U160::ZERO,
             )
             .block(synced_block.into())
             .call()
             .await
             .unwrap();
 
-        assert_eq!(amount_out_1, expected_amount_out_1.amountOut);
+        assert_eq!(amount_out_1, expected_amount_out_1);
 
         let amount_in_2 = U256::from(10000000000000_u128); // 10_000_000 USDC
         let amount_out_2 = pool.simulate_swap(pool.token_a, amount_in_2).unwrap();
@@ -1641,16 +1688,16 @@ mod test {
             .quoteExactInputSingle(
                 pool.token_a,
                 pool.token_b,
-                pool.fee,
+                U24::from(pool.fee),
                 amount_in_2,
-                U256::ZERO,
+                U160::ZERO,
             )
             .block(synced_block.into())
             .call()
             .await
             .unwrap();
 
-        assert_eq!(amount_out_2, expected_amount_out_2.amountOut);
+        assert_eq!(amount_out_2, expected_amount_out_2);
 
         let amount_in_3 = U256::from(100000000000000_u128); // 100_000_000 USDC
         let amount_out_3 = pool.simulate_swap(pool.token_a, amount_in_3).unwrap();
@@ -1658,23 +1705,23 @@ mod test {
             .quoteExactInputSingle(
                 pool.token_a,
                 pool.token_b,
-                pool.fee,
+                U24::from(pool.fee),
                 amount_in_3,
-                U256::ZERO,
+                U160::ZERO,
             )
             .block(synced_block.into())
             .call()
             .await
             .unwrap();
 
-        assert_eq!(amount_out_3, expected_amount_out_3.amountOut);
+        assert_eq!(amount_out_3, expected_amount_out_3);
     }
 
     #[tokio::test]
     #[ignore] // Ignoring to not throttle the Provider on workflows
     async fn test_simulate_swap_weth_usdc() {
         let rpc_endpoint = std::env::var("ETHEREUM_RPC_ENDPOINT").unwrap();
-        let provider = Arc::new(ProviderBuilder::new().on_http(rpc_endpoint.parse().unwrap()));
+        let provider = Arc::new(ProviderBuilder::new().connect_http(rpc_endpoint.parse().unwrap()));
 
         let (pool, synced_block) = initialize_usdc_weth_pool(provider.clone()).await.unwrap();
         let quoter = IQuoter::new(
@@ -1685,13 +1732,19 @@ mod test {
         let amount_in = U256::from(1000000000000000000_u128); // 1 ETH
         let amount_out = pool.simulate_swap(pool.token_b, amount_in).unwrap();
         let expected_amount_out = quoter
-            .quoteExactInputSingle(pool.token_b, pool.token_a, pool.fee, amount_in, U256::ZERO)
+            .quoteExactInputSingle(
+                pool.token_b,
+                pool.token_a,
+                U24::from(pool.fee),
+                amount_in,
+                U160::ZERO,
+            )
             .block(synced_block.into())
             .call()
             .await
             .unwrap();
 
-        assert_eq!(amount_out, expected_amount_out.amountOut);
+        assert_eq!(amount_out, expected_amount_out);
 
         let amount_in_1 = U256::from(10000000000000000000_u128); // 10 ETH
         let amount_out_1 = pool.simulate_swap(pool.token_b, amount_in_1).unwrap();
@@ -1699,16 +1752,16 @@ mod test {
             .quoteExactInputSingle(
                 pool.token_b,
                 pool.token_a,
-                pool.fee,
+                U24::from(pool.fee),
                 amount_in_1,
-                U256::ZERO,
+                U160::ZERO,
             )
             .block(synced_block.into())
             .call()
             .await
             .unwrap();
 
-        assert_eq!(amount_out_1, expected_amount_out_1.amountOut);
+        assert_eq!(amount_out_1, expected_amount_out_1);
 
         let amount_in_2 = U256::from(100000000000000000000_u128); // 100 ETH
         let amount_out_2 = pool.simulate_swap(pool.token_b, amount_in_2).unwrap();
@@ -1716,16 +1769,16 @@ mod test {
             .quoteExactInputSingle(
                 pool.token_b,
                 pool.token_a,
-                pool.fee,
+                U24::from(pool.fee),
                 amount_in_2,
-                U256::ZERO,
+                U160::ZERO,
             )
             .block(synced_block.into())
             .call()
             .await
             .unwrap();
 
-        assert_eq!(amount_out_2, expected_amount_out_2.amountOut);
+        assert_eq!(amount_out_2, expected_amount_out_2);
 
         let amount_in_3 = U256::from(100000000000000000000_u128); // 100_000 ETH
         let amount_out_3 = pool.simulate_swap(pool.token_b, amount_in_3).unwrap();
@@ -1733,23 +1786,23 @@ mod test {
             .quoteExactInputSingle(
                 pool.token_b,
                 pool.token_a,
-                pool.fee,
+                U24::from(pool.fee),
                 amount_in_3,
-                U256::ZERO,
+                U160::ZERO,
             )
             .block(synced_block.into())
             .call()
             .await
             .unwrap();
 
-        assert_eq!(amount_out_3, expected_amount_out_3.amountOut);
+        assert_eq!(amount_out_3, expected_amount_out_3);
     }
 
     #[tokio::test]
     #[ignore] // Ignoring to not throttle the Provider on workflows
     async fn test_simulate_swap_link_weth() {
         let rpc_endpoint = std::env::var("ETHEREUM_RPC_ENDPOINT").unwrap();
-        let provider = Arc::new(ProviderBuilder::new().on_http(rpc_endpoint.parse().unwrap()));
+        let provider = Arc::new(ProviderBuilder::new().connect_http(rpc_endpoint.parse().unwrap()));
 
         let (pool, synced_block) = initialize_weth_link_pool(provider.clone()).await.unwrap();
         let quoter = IQuoter::new(
@@ -1760,13 +1813,19 @@ mod test {
         let amount_in = U256::from(1000000000000000000_u128); // 1 LINK
         let amount_out = pool.simulate_swap(pool.token_a, amount_in).unwrap();
         let expected_amount_out = quoter
-            .quoteExactInputSingle(pool.token_a, pool.token_b, pool.fee, amount_in, U256::ZERO)
+            .quoteExactInputSingle(
+                pool.token_a,
+                pool.token_b,
+                U24::from(pool.fee),
+                amount_in,
+                U160::ZERO,
+            )
             .block(synced_block.into())
             .call()
             .await
             .unwrap();
 
-        assert_eq!(amount_out, expected_amount_out.amountOut);
+        assert_eq!(amount_out, expected_amount_out);
 
         let amount_in_1 = U256::from(100000000000000000000_u128); // 100 LINK
         let amount_out_1 = pool.simulate_swap(pool.token_a, amount_in_1).unwrap();
@@ -1774,16 +1833,16 @@ mod test {
             .quoteExactInputSingle(
                 pool.token_a,
                 pool.token_b,
-                pool.fee,
+                U24::from(pool.fee),
                 amount_in_1,
-                U256::ZERO,
+                U160::ZERO,
             )
             .block(synced_block.into())
             .call()
             .await
             .unwrap();
 
-        assert_eq!(amount_out_1, expected_amount_out_1.amountOut);
+        assert_eq!(amount_out_1, expected_amount_out_1);
 
         let amount_in_2 = U256::from(10000000000000000000000_u128); // 10_000 LINK
         let amount_out_2 = pool.simulate_swap(pool.token_a, amount_in_2).unwrap();
@@ -1791,16 +1850,16 @@ mod test {
             .quoteExactInputSingle(
                 pool.token_a,
                 pool.token_b,
-                pool.fee,
+                U24::from(pool.fee),
                 amount_in_2,
-                U256::ZERO,
+                U160::ZERO,
             )
             .block(synced_block.into())
             .call()
             .await
             .unwrap();
 
-        assert_eq!(amount_out_2, expected_amount_out_2.amountOut);
+        assert_eq!(amount_out_2, expected_amount_out_2);
 
         let amount_in_3 = U256::from(10000000000000000000000_u128); // 1_000_000 LINK
         let amount_out_3 = pool.simulate_swap(pool.token_a, amount_in_3).unwrap();
@@ -1808,23 +1867,23 @@ mod test {
             .quoteExactInputSingle(
                 pool.token_a,
                 pool.token_b,
-                pool.fee,
+                U24::from(pool.fee),
                 amount_in_3,
-                U256::ZERO,
+                U160::ZERO,
             )
             .block(synced_block.into())
             .call()
             .await
             .unwrap();
 
-        assert_eq!(amount_out_3, expected_amount_out_3.amountOut);
+        assert_eq!(amount_out_3, expected_amount_out_3);
     }
 
     #[tokio::test]
     #[ignore] // Ignoring to not throttle the Provider on workflows
     async fn test_simulate_swap_weth_link() {
         let rpc_endpoint = std::env::var("ETHEREUM_RPC_ENDPOINT").unwrap();
-        let provider = Arc::new(ProviderBuilder::new().on_http(rpc_endpoint.parse().unwrap()));
+        let provider = Arc::new(ProviderBuilder::new().connect_http(rpc_endpoint.parse().unwrap()));
 
         let (pool, synced_block) = initialize_weth_link_pool(provider.clone()).await.unwrap();
         let quoter = IQuoter::new(
@@ -1835,13 +1894,19 @@ mod test {
         let amount_in = U256::from(1000000000000000000_u128); // 1 ETH
         let amount_out = pool.simulate_swap(pool.token_b, amount_in).unwrap();
         let expected_amount_out = quoter
-            .quoteExactInputSingle(pool.token_b, pool.token_a, pool.fee, amount_in, U256::ZERO)
+            .quoteExactInputSingle(
+                pool.token_b,
+                pool.token_a,
+                U24::from(pool.fee),
+                amount_in,
+                U160::ZERO,
+            )
             .block(synced_block.into())
             .call()
             .await
             .unwrap();
 
-        assert_eq!(amount_out, expected_amount_out.amountOut);
+        assert_eq!(amount_out, expected_amount_out);
 
         let amount_in_1 = U256::from(10000000000000000000_u128); // 10 ETH
         let amount_out_1 = pool.simulate_swap(pool.token_b, amount_in_1).unwrap();
@@ -1849,16 +1914,16 @@ mod test {
             .quoteExactInputSingle(
                 pool.token_b,
                 pool.token_a,
-                pool.fee,
+                U24::from(pool.fee),
                 amount_in_1,
-                U256::ZERO,
+                U160::ZERO,
             )
             .block(synced_block.into())
             .call()
             .await
             .unwrap();
 
-        assert_eq!(amount_out_1, expected_amount_out_1.amountOut);
+        assert_eq!(amount_out_1, expected_amount_out_1);
 
         let amount_in_2 = U256::from(100000000000000000000_u128); // 100 ETH
         let amount_out_2 = pool.simulate_swap(pool.token_b, amount_in_2).unwrap();
@@ -1866,16 +1931,16 @@ mod test {
             .quoteExactInputSingle(
                 pool.token_b,
                 pool.token_a,
-                pool.fee,
+                U24::from(pool.fee),
                 amount_in_2,
-                U256::ZERO,
+                U160::ZERO,
             )
             .block(synced_block.into())
             .call()
             .await
             .unwrap();
 
-        assert_eq!(amount_out_2, expected_amount_out_2.amountOut);
+        assert_eq!(amount_out_2, expected_amount_out_2);
 
         let amount_in_3 = U256::from(100000000000000000000_u128); // 100_000 ETH
         let amount_out_3 = pool.simulate_swap(pool.token_b, amount_in_3).unwrap();
@@ -1883,23 +1948,23 @@ mod test {
             .quoteExactInputSingle(
                 pool.token_b,
                 pool.token_a,
-                pool.fee,
+                U24::from(pool.fee),
                 amount_in_3,
-                U256::ZERO,
+                U160::ZERO,
             )
             .block(synced_block.into())
             .call()
             .await
             .unwrap();
 
-        assert_eq!(amount_out_3, expected_amount_out_3.amountOut);
+        assert_eq!(amount_out_3, expected_amount_out_3);
     }
 
     #[tokio::test]
     #[ignore] // Ignoring to not throttle the Provider on workflows
     async fn test_simulate_swap_mut_usdc_weth() {
         let rpc_endpoint = std::env::var("ETHEREUM_RPC_ENDPOINT").unwrap();
-        let provider = Arc::new(ProviderBuilder::new().on_http(rpc_endpoint.parse().unwrap()));
+        let provider = Arc::new(ProviderBuilder::new().connect_http(rpc_endpoint.parse().unwrap()));
 
         let (pool, synced_block) = initialize_usdc_weth_pool(provider.clone()).await.unwrap();
         let quoter = IQuoter::new(
@@ -1910,13 +1975,19 @@ mod test {
         let amount_in = U256::from(100000000_u64); // 100 USDC
         let amount_out = pool.simulate_swap(pool.token_a, amount_in).unwrap();
         let expected_amount_out = quoter
-            .quoteExactInputSingle(pool.token_a, pool.token_b, pool.fee, amount_in, U256::ZERO)
+            .quoteExactInputSingle(
+                pool.token_a,
+                pool.token_b,
+                U24::from(pool.fee),
+                amount_in,
+                U160::ZERO,
+            )
             .block(synced_block.into())
             .call()
             .await
             .unwrap();
 
-        assert_eq!(amount_out, expected_amount_out.amountOut);
+        assert_eq!(amount_out, expected_amount_out);
 
         let amount_in_1 = U256::from(10000000000_u128); // 10_000 USDC
         let amount_out_1 = pool.simulate_swap(pool.token_a, amount_in_1).unwrap();
@@ -1924,16 +1995,16 @@ mod test {
             .quoteExactInputSingle(
                 pool.token_a,
                 pool.token_b,
-                pool.fee,
+                U24::from(pool.fee),
                 amount_in_1,
-                U256::ZERO,
+                U160::ZERO,
             )
             .block(synced_block.into())
             .call()
             .await
             .unwrap();
 
-        assert_eq!(amount_out_1, expected_amount_out_1.amountOut);
+        assert_eq!(amount_out_1, expected_amount_out_1);
 
         let amount_in_2 = U256::from(10000000000000_u128); // 10_000_000 USDC
         let amount_out_2 = pool.simulate_swap(pool.token_a, amount_in_2).unwrap();
@@ -1941,16 +2012,16 @@ mod test {
             .quoteExactInputSingle(
                 pool.token_a,
                 pool.token_b,
-                pool.fee,
+                U24::from(pool.fee),
                 amount_in_2,
-                U256::ZERO,
+                U160::ZERO,
             )
             .block(synced_block.into())
             .call()
             .await
             .unwrap();
 
-        assert_eq!(amount_out_2, expected_amount_out_2.amountOut);
+        assert_eq!(amount_out_2, expected_amount_out_2);
 
         let amount_in_3 = U256::from(100000000000000_u128); // 100_000_000 USDC
         let amount_out_3 = pool.simulate_swap(pool.token_a, amount_in_3).unwrap();
@@ -1958,23 +2029,23 @@ mod test {
             .quoteExactInputSingle(
                 pool.token_a,
                 pool.token_b,
-                pool.fee,
+                U24::from(pool.fee),
                 amount_in_3,
-                U256::ZERO,
+                U160::ZERO,
             )
             .block(synced_block.into())
             .call()
             .await
             .unwrap();
 
-        assert_eq!(amount_out_3, expected_amount_out_3.amountOut);
+        assert_eq!(amount_out_3, expected_amount_out_3);
     }
 
     #[tokio::test]
     #[ignore] // Ignoring to not throttle the Provider on workflows
     async fn test_simulate_swap_mut_weth_usdc() {
         let rpc_endpoint = std::env::var("ETHEREUM_RPC_ENDPOINT").unwrap();
-        let provider = Arc::new(ProviderBuilder::new().on_http(rpc_endpoint.parse().unwrap()));
+        let provider = Arc::new(ProviderBuilder::new().connect_http(rpc_endpoint.parse().unwrap()));
 
         let (pool, synced_block) = initialize_usdc_weth_pool(provider.clone()).await.unwrap();
         let quoter = IQuoter::new(
@@ -1985,13 +2056,19 @@ mod test {
         let amount_in = U256::from(1000000000000000000_u128); // 1 ETH
         let amount_out = pool.simulate_swap(pool.token_b, amount_in).unwrap();
         let expected_amount_out = quoter
-            .quoteExactInputSingle(pool.token_b, pool.token_a, pool.fee, amount_in, U256::ZERO)
+            .quoteExactInputSingle(
+                pool.token_b,
+                pool.token_a,
+                U24::from(pool.fee),
+                amount_in,
+                U160::ZERO,
+            )
             .block(synced_block.into())
             .call()
             .await
             .unwrap();
 
-        assert_eq!(amount_out, expected_amount_out.amountOut);
+        assert_eq!(amount_out, expected_amount_out);
 
         let amount_in_1 = U256::from(10000000000000000000_u128); // 10 ETH
         let amount_out_1 = pool.simulate_swap(pool.token_b, amount_in_1).unwrap();
@@ -1999,16 +2076,16 @@ mod test {
             .quoteExactInputSingle(
                 pool.token_b,
                 pool.token_a,
-                pool.fee,
+                U24::from(pool.fee),
                 amount_in_1,
-                U256::ZERO,
+                U160::ZERO,
             )
             .block(synced_block.into())
             .call()
             .await
             .unwrap();
 
-        assert_eq!(amount_out_1, expected_amount_out_1.amountOut);
+        assert_eq!(amount_out_1, expected_amount_out_1);
 
         let amount_in_2 = U256::from(100000000000000000000_u128); // 100 ETH
         let amount_out_2 = pool.simulate_swap(pool.token_b, amount_in_2).unwrap();
@@ -2016,16 +2093,16 @@ mod test {
             .quoteExactInputSingle(
                 pool.token_b,
                 pool.token_a,
-                pool.fee,
+                U24::from(pool.fee),
                 amount_in_2,
-                U256::ZERO,
+                U160::ZERO,
             )
             .block(synced_block.into())
             .call()
             .await
             .unwrap();
 
-        assert_eq!(amount_out_2, expected_amount_out_2.amountOut);
+        assert_eq!(amount_out_2, expected_amount_out_2);
 
         let amount_in_3 = U256::from(100000000000000000000_u128); // 100_000 ETH
         let amount_out_3 = pool.simulate_swap(pool.token_b, amount_in_3).unwrap();
@@ -2033,23 +2110,23 @@ mod test {
             .quoteExactInputSingle(
                 pool.token_b,
                 pool.token_a,
-                pool.fee,
+                U24::from(pool.fee),
                 amount_in_3,
-                U256::ZERO,
+                U160::ZERO,
             )
             .block(synced_block.into())
             .call()
             .await
             .unwrap();
 
-        assert_eq!(amount_out_3, expected_amount_out_3.amountOut);
+        assert_eq!(amount_out_3, expected_amount_out_3);
     }
 
     #[tokio::test]
     #[ignore] // Ignoring to not throttle the Provider on workflows
     async fn test_simulate_swap_mut_link_weth() {
         let rpc_endpoint = std::env::var("ETHEREUM_RPC_ENDPOINT").unwrap();
-        let provider = Arc::new(ProviderBuilder::new().on_http(rpc_endpoint.parse().unwrap()));
+        let provider = Arc::new(ProviderBuilder::new().connect_http(rpc_endpoint.parse().unwrap()));
 
         let (pool, synced_block) = initialize_weth_link_pool(provider.clone()).await.unwrap();
         let quoter = IQuoter::new(
@@ -2060,13 +2137,19 @@ mod test {
         let amount_in = U256::from(1000000000000000000_u128); // 1 LINK
         let amount_out = pool.simulate_swap(pool.token_a, amount_in).unwrap();
         let expected_amount_out = quoter
-            .quoteExactInputSingle(pool.token_a, pool.token_b, pool.fee, amount_in, U256::ZERO)
+            .quoteExactInputSingle(
+                pool.token_a,
+                pool.token_b,
+                U24::from(pool.fee),
+                amount_in,
+                U160::ZERO,
+            )
             .block(synced_block.into())
             .call()
             .await
             .unwrap();
 
-        assert_eq!(amount_out, expected_amount_out.amountOut);
+        assert_eq!(amount_out, expected_amount_out);
 
         let amount_in_1 = U256::from(100000000000000000000_u128); // 100 LINK
         let amount_out_1 = pool.simulate_swap(pool.token_a, amount_in_1).unwrap();
@@ -2074,16 +2157,16 @@ mod test {
             .quoteExactInputSingle(
                 pool.token_a,
                 pool.token_b,
-                pool.fee,
+                U24::from(pool.fee),
                 amount_in_1,
-                U256::ZERO,
+                U160::ZERO,
             )
             .block(synced_block.into())
             .call()
             .await
             .unwrap();
 
-        assert_eq!(amount_out_1, expected_amount_out_1.amountOut);
+        assert_eq!(amount_out_1, expected_amount_out_1);
 
         let amount_in_2 = U256::from(10000000000000000000000_u128); // 10_000 LINK
         let amount_out_2 = pool.simulate_swap(pool.token_a, amount_in_2).unwrap();
@@ -2091,16 +2174,16 @@ mod test {
             .quoteExactInputSingle(
                 pool.token_a,
                 pool.token_b,
-                pool.fee,
+                U24::from(pool.fee),
                 amount_in_2,
-                U256::ZERO,
+                U160::ZERO,
             )
             .block(synced_block.into())
             .call()
             .await
             .unwrap();
 
-        assert_eq!(amount_out_2, expected_amount_out_2.amountOut);
+        assert_eq!(amount_out_2, expected_amount_out_2);
 
         let amount_in_3 = U256::from(10000000000000000000000_u128); // 1_000_000 LINK
         let amount_out_3 = pool.simulate_swap(pool.token_a, amount_in_3).unwrap();
@@ -2108,23 +2191,23 @@ mod test {
             .quoteExactInputSingle(
                 pool.token_a,
                 pool.token_b,
-                pool.fee,
+                U24::from(pool.fee),
                 amount_in_3,
-                U256::ZERO,
+                U160::ZERO,
             )
             .block(synced_block.into())
             .call()
             .await
             .unwrap();
 
-        assert_eq!(amount_out_3, expected_amount_out_3.amountOut);
+        assert_eq!(amount_out_3, expected_amount_out_3);
     }
 
     #[tokio::test]
     #[ignore] // Ignoring to not throttle the Provider on workflows
     async fn test_simulate_swap_mut_weth_link() {
         let rpc_endpoint = std::env::var("ETHEREUM_RPC_ENDPOINT").unwrap();
-        let provider = Arc::new(ProviderBuilder::new().on_http(rpc_endpoint.parse().unwrap()));
+        let provider = Arc::new(ProviderBuilder::new().connect_http(rpc_endpoint.parse().unwrap()));
 
         let (pool, synced_block) = initialize_weth_link_pool(provider.clone()).await.unwrap();
         let quoter = IQuoter::new(
@@ -2135,13 +2218,19 @@ mod test {
         let amount_in = U256::from(1000000000000000000_u128); // 1 ETH
         let amount_out = pool.simulate_swap(pool.token_b, amount_in).unwrap();
         let expected_amount_out = quoter
-            .quoteExactInputSingle(pool.token_b, pool.token_a, pool.fee, amount_in, U256::ZERO)
+            .quoteExactInputSingle(
+                pool.token_b,
+                pool.token_a,
+                U24::from(pool.fee),
+                amount_in,
+                U160::ZERO,
+            )
             .block(synced_block.into())
             .call()
             .await
             .unwrap();
 
-        assert_eq!(amount_out, expected_amount_out.amountOut);
+        assert_eq!(amount_out, expected_amount_out);
 
         let amount_in_1 = U256::from(10000000000000000000_u128); // 10 ETH
         let amount_out_1 = pool.simulate_swap(pool.token_b, amount_in_1).unwrap();
@@ -2149,16 +2238,16 @@ mod test {
             .quoteExactInputSingle(
                 pool.token_b,
                 pool.token_a,
-                pool.fee,
+                U24::from(pool.fee),
                 amount_in_1,
-                U256::ZERO,
+                U160::ZERO,
             )
             .block(synced_block.into())
             .call()
             .await
             .unwrap();
 
-        assert_eq!(amount_out_1, expected_amount_out_1.amountOut);
+        assert_eq!(amount_out_1, expected_amount_out_1);
 
         let amount_in_2 = U256::from(100000000000000000000_u128); // 100 ETH
         let amount_out_2 = pool.simulate_swap(pool.token_b, amount_in_2).unwrap();
@@ -2166,16 +2255,16 @@ mod test {
             .quoteExactInputSingle(
                 pool.token_b,
                 pool.token_a,
-                pool.fee,
+                U24::from(pool.fee),
                 amount_in_2,
-                U256::ZERO,
+                U160::ZERO,
             )
             .block(synced_block.into())
             .call()
             .await
             .unwrap();
 
-        assert_eq!(amount_out_2, expected_amount_out_2.amountOut);
+        assert_eq!(amount_out_2, expected_amount_out_2);
 
         let amount_in_3 = U256::from(100000000000000000000_u128); // 100_000 ETH
         let amount_out_3 = pool.simulate_swap(pool.token_b, amount_in_3).unwrap();
@@ -2183,23 +2272,23 @@ mod test {
             .quoteExactInputSingle(
                 pool.token_b,
                 pool.token_a,
-                pool.fee,
+                U24::from(pool.fee),
                 amount_in_3,
-                U256::ZERO,
+                U160::ZERO,
             )
             .block(synced_block.into())
             .call()
             .await
             .unwrap();
 
-        assert_eq!(amount_out_3, expected_amount_out_3.amountOut);
+        assert_eq!(amount_out_3, expected_amount_out_3);
     }
 
     #[tokio::test]
     #[ignore] // Ignoring to not throttle the Provider on workflows
     async fn test_get_new_from_address() {
         let rpc_endpoint = std::env::var("ETHEREUM_RPC_ENDPOINT").unwrap();
-        let provider = Arc::new(ProviderBuilder::new().on_http(rpc_endpoint.parse().unwrap()));
+        let provider = Arc::new(ProviderBuilder::new().connect_http(rpc_endpoint.parse().unwrap()));
 
         let pool = UniswapV3Pool::new_from_address(
             address!("88e6A0c2dDD26FEEb64F039a2c41296FcB3f5640"),
@@ -2232,7 +2321,7 @@ mod test {
     #[ignore] // Ignoring to not throttle the Provider on workflows
     async fn test_get_pool_data() {
         let rpc_endpoint = std::env::var("ETHEREUM_RPC_ENDPOINT").unwrap();
-        let provider = Arc::new(ProviderBuilder::new().on_http(rpc_endpoint.parse().unwrap()));
+        let provider = Arc::new(ProviderBuilder::new().connect_http(rpc_endpoint.parse().unwrap()));
 
         let (pool, _synced_block) = initialize_usdc_weth_pool(provider.clone()).await.unwrap();
         assert_eq!(
@@ -2257,7 +2346,7 @@ mod test {
     #[tokio::test]
     async fn test_sync_pool() {
         let rpc_endpoint = std::env::var("ETHEREUM_RPC_ENDPOINT").unwrap();
-        let provider = Arc::new(ProviderBuilder::new().on_http(rpc_endpoint.parse().unwrap()));
+        let provider = Arc::new(ProviderBuilder::new().connect_http(rpc_endpoint.parse().unwrap()));
 
         let mut pool = UniswapV3Pool {
             address: address!("88e6A0c2dDD26FEEb64F039a2c41296FcB3f5640"),
@@ -2272,7 +2361,7 @@ mod test {
     #[tokio::test]
     async fn test_calculate_virtual_reserves() {
         let rpc_endpoint = std::env::var("ETHEREUM_RPC_ENDPOINT").unwrap();
-        let provider = Arc::new(ProviderBuilder::new().on_http(rpc_endpoint.parse().unwrap()));
+        let provider = Arc::new(ProviderBuilder::new().connect_http(rpc_endpoint.parse().unwrap()));
 
         let mut pool = UniswapV3Pool {
             address: address!("88e6A0c2dDD26FEEb64F039a2c41296FcB3f5640"),
@@ -2300,8 +2389,8 @@ mod test {
             .await
             .unwrap();
 
-        pool.sqrt_price = sqrt_price._0;
-        pool.liquidity = liquidity._0;
+        pool.sqrt_price = U256::from(sqrt_price._0);
+        pool.liquidity = liquidity;
 
         let (r_0, r_1) = pool.calculate_virtual_reserves().unwrap();
 
@@ -2312,7 +2401,7 @@ mod test {
     #[tokio::test]
     async fn test_calculate_price() {
         let rpc_endpoint = std::env::var("ETHEREUM_RPC_ENDPOINT").unwrap();
-        let provider = Arc::new(ProviderBuilder::new().on_http(rpc_endpoint.parse().unwrap()));
+        let provider = Arc::new(ProviderBuilder::new().connect_http(rpc_endpoint.parse().unwrap()));
 
         let mut pool = UniswapV3Pool {
             address: address!("88e6A0c2dDD26FEEb64F039a2c41296FcB3f5640"),
@@ -2333,7 +2422,7 @@ mod test {
             .await
             .unwrap();
 
-        pool.sqrt_price = sqrt_price._0;
+        pool.sqrt_price = U256::from(sqrt_price._0);
 
         let float_price_a = pool.calculate_price(pool.token_a).unwrap();
         let float_price_b = pool.calculate_price(pool.token_b).unwrap();
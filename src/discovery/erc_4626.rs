@@ -7,7 +7,10 @@ use alloy::{
 use regex::Regex;
 
 use crate::{
-    amm::erc_4626::{ERC4626Vault, IERC4626Vault},
+    amm::{
+        erc_4626::{ERC4626Vault, IERC4626Vault},
+        AutomatedMarketMaker,
+    },
     errors::AMMError,
 };
 
@@ -20,6 +23,26 @@ pub async fn discover_erc_4626_vaults<N, P>(
     provider: Arc<P>,
     step: u64,
 ) -> Result<Vec<ERC4626Vault>, AMMError>
+where
+    N: Network,
+    P: Provider<N>,
+{
+    let current_block = provider.get_block_number().await?;
+
+    discover_erc_4626_vaults_in_range(0, current_block, step, provider).await
+}
+
+/// Discovers ERC4626 vaults that emitted both deposit and withdraw events between `from_block` and `to_block` inclusive,
+/// populating each vault as of `to_block`.
+///
+/// Vaults that only emitted one of the events within the range are not discovered, so a short range, e.g. the blocks since
+/// a checkpoint, may miss vaults until they emit the other event in a later range.
+pub async fn discover_erc_4626_vaults_in_range<N, P>(
+    mut from_block: u64,
+    to_block: u64,
+    step: u64,
+    provider: Arc<P>,
+) -> Result<Vec<ERC4626Vault>, AMMError>
 where
     N: Network,
     P: Provider<N>,
@@ -31,27 +54,21 @@ where
     let block_filter = Filter::new().event_signature(event_signatures.clone());
     tracing::trace!(?event_signatures);
 
-    let current_block = provider.get_block_number().await?;
-
     let mut adheres_to_withdraw_event = HashSet::new();
     let mut adheres_to_deposit_event = HashSet::new();
     let mut identified_addresses = HashSet::new();
 
-    let mut from_block = 0;
     // TODO: make this async
-    while from_block < current_block {
+    while from_block <= to_block {
         // Get pair created event logs within the block range
-        let mut to_block = from_block + step - 1;
-        if to_block > current_block {
-            to_block = current_block;
-        }
+        let batch_to_block = (from_block + step - 1).min(to_block);
 
         let block_filter = block_filter.clone();
         // TODO: use a better method, this is just quick and scrappy
         let fallback_block_filter = block_filter.clone();
 
         let logs = match provider
-            .get_logs(&block_filter.from_block(from_block).to_block(to_block))
+            .get_logs(&block_filter.from_block(from_block).to_block(batch_to_block))
             .await
         {
             Ok(logs) => {
@@ -107,11 +124,19 @@ where
 
     let mut vaults = vec![];
     for identified_address in identified_addresses {
-        //TODO: Add an interface check, but for now just try to get a new vault from address, if it fails then do not add it to the identified
+        //TODO: Add an interface check, but for now just try to populate a new vault from address, if it fails then do not add it to the identified
         //TODO: vaults. This approach is inefficient but should work for now.
 
-        if let Ok(vault) =
-            ERC4626Vault::new_from_address(*identified_address, provider.clone()).await
+        let mut vault = ERC4626Vault {
+            vault_token: *identified_address,
+            ..Default::default()
+        };
+
+        if vault
+            .populate_data(Some(to_block), provider.clone())
+            .await
+            .is_ok()
+            && vault.data_is_populated()
         {
             vaults.push(vault);
         }
@@ -80,6 +80,8 @@ pub enum EventLogError {
     InvalidEventSignature,
     #[error("Log Block number not found")]
     LogBlockNumberNotFound,
+    #[error("Log Block hash not found")]
+    LogBlockHashNotFound,
     #[error(transparent)]
     EthABIError(#[from] alloy::sol_types::Error),
     #[error(transparent)]
@@ -108,4 +110,16 @@ pub enum CheckpointError {
     SerdeJsonError(#[from] serde_json::error::Error),
     #[error(transparent)]
     IOError(#[from] std::io::Error),
+    #[error(transparent)]
+    BincodeError(#[from] bincode::Error),
+    #[error("Unsupported checkpoint version {0}")]
+    UnsupportedVersion(u16),
+    #[error("Unknown checkpoint compression {0}")]
+    UnknownCompression(u8),
+    #[error("Checkpoint delta from block {0} does not apply to checkpoint at block {1}")]
+    DeltaGap(u64, u64),
+    #[error("Checkpoint was synced on chain {0}, but the provider is connected to chain {1}")]
+    ChainIdMismatch(u64, u64),
+    #[error("Checkpoint block {0} is no longer canonical, so the Uniswap V3 ticks in the checkpoint can not be synced")]
+    NonCanonicalTicks(u64),
 }
@@ -229,7 +229,7 @@ mod test {
     async fn test_weth_value_filter() {
         let ipc_endpoint = std::env::var("WS").unwrap();
         let ws = WsConnect::new(ipc_endpoint.to_owned());
-        let provider = Arc::new(ProviderBuilder::new().on_ws(ws).await.unwrap());
+        let provider = Arc::new(ProviderBuilder::new().connect_ws(ws).await.unwrap());
 
         let factories = vec![
             // Add Uniswap V2
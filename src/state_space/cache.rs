@@ -1,58 +1,209 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 
-use arraydeque::{ArrayDeque, CapacityError};
+use alloy::primitives::{Address, B256};
 
-use super::StateChange;
+use super::{error::StateSpaceError, StateChange};
 use crate::amm::{AutomatedMarketMaker, AMM};
 
+/// Default number of state changes and block hashes retained for unwinding reorgs
+pub const DEFAULT_CACHE_DEPTH: usize = 150;
+
+// Per chain cache depth presets
+pub const ETHEREUM_CACHE_DEPTH: usize = 150;
+pub const POLYGON_CACHE_DEPTH: usize = 512;
+pub const BSC_CACHE_DEPTH: usize = 64;
+pub const L2_CACHE_DEPTH: usize = 16;
+
+/// Returns the cache depth preset for the chain id, falling back to `DEFAULT_CACHE_DEPTH` for unknown chains
+pub fn cache_depth_for_chain(chain_id: u64) -> usize {
+    match chain_id {
+        // Ethereum
+        1 => ETHEREUM_CACHE_DEPTH,
+        // Polygon PoS is prone to deep reorgs
+        137 => POLYGON_CACHE_DEPTH,
+        // BNB Smart Chain
+        56 => BSC_CACHE_DEPTH,
+        // Optimism, Base and Arbitrum One are sequenced and rarely reorg
+        10 | 8453 | 42161 => L2_CACHE_DEPTH,
+        _ => DEFAULT_CACHE_DEPTH,
+    }
+}
+
 #[derive(Debug)]
 
 pub struct StateChangeCache {
     oldest_block: u64,
-    cache: ArrayDeque<StateChange, 150>,
+    depth: usize,
+    cache: VecDeque<StateChange>,
+    /// Block number and hash of each block the state space has been synced through, newest first
+    block_hashes: VecDeque<(u64, B256)>,
+    /// Block number and address of each AMM created by a tracked factory, newest first
+    created_amms: VecDeque<(u64, Address)>,
+}
+
+impl Default for StateChangeCache {
+    fn default() -> Self {
+        StateChangeCache::new()
+    }
 }
 
 impl StateChangeCache {
     pub fn new() -> Self {
+        StateChangeCache::with_depth(DEFAULT_CACHE_DEPTH)
+    }
+
+    /// Creates a cache that retains at most `depth` state changes and block hashes, with a depth of 0 clamped to 1
+    pub fn with_depth(depth: usize) -> Self {
         StateChangeCache {
             oldest_block: 0,
-            cache: ArrayDeque::new(),
+            depth: depth.max(1),
+            cache: VecDeque::new(),
+            block_hashes: VecDeque::new(),
+            created_amms: VecDeque::new(),
         }
     }
 
+    /// Creates a cache with the depth preset for the chain id
+    pub fn for_chain(chain_id: u64) -> Self {
+        StateChangeCache::with_depth(cache_depth_for_chain(chain_id))
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
     pub fn is_empty(&self) -> bool {
         self.cache.is_empty()
     }
 
+    /// Adds the state change to the front of the cache, evicting the oldest state change once the cache is at depth.
+    ///
+    /// Returns `StateSpaceError::StateChangeOutOfOrder` if the state change is older than the latest cached state change.
     pub fn add_state_change_to_cache(
         &mut self,
         state_change: StateChange,
-    ) -> Result<(), CapacityError<StateChange>> {
+    ) -> Result<(), StateSpaceError> {
         let cache = &mut self.cache;
 
-        if cache.is_full() {
+        if let Some(latest) = cache.front() {
+            if state_change.block_number < latest.block_number {
+                return Err(StateSpaceError::StateChangeOutOfOrder(
+                    state_change.block_number,
+                    latest.block_number,
+                ));
+            }
+        }
+
+        if cache.len() >= self.depth {
             cache.pop_back();
-            self.oldest_block = cache.back().unwrap().block_number;
+            // If the cache is now empty, the incoming state change becomes the oldest
+            self.oldest_block = cache
+                .back()
+                .map_or(state_change.block_number, |oldest| oldest.block_number);
+        }
+
+        cache.push_front(state_change);
+
+        Ok(())
+    }
+
+    /// Records the hash of a block that the state space has been synced through.
+    /// Any previously recorded blocks at or above `block_number` are replaced.
+    pub fn add_block_hash(&mut self, block_number: u64, block_hash: B256) {
+        let block_hashes = &mut self.block_hashes;
+
+        while block_hashes
+            .front()
+            .is_some_and(|(number, _)| *number >= block_number)
+        {
+            block_hashes.pop_front();
+        }
+
+        if block_hashes.len() >= self.depth {
+            block_hashes.pop_back();
+        }
+
+        block_hashes.push_front((block_number, block_hash));
+    }
+
+    /// Records an AMM created at `block_number`, so that it is removed if the block is unwound.
+    /// Creations older than the cache depth are discarded, since blocks that old can not be unwound.
+    pub fn add_created_amm(&mut self, block_number: u64, amm_address: Address) {
+        let created_amms = &mut self.created_amms;
+
+        while created_amms
+            .back()
+            .is_some_and(|(number, _)| *number + (self.depth as u64) < block_number)
+        {
+            created_amms.pop_back();
+        }
+
+        created_amms.push_front((block_number, amm_address));
+    }
+
+    /// Removes and returns the addresses of the AMMs created at or above the given block number
+    pub fn unwind_created_amms(&mut self, block_to_unwind: u64) -> Vec<Address> {
+        let mut amm_addresses = vec![];
+        while let Some((_, amm_address)) = self
+            .created_amms
+            .front()
+            .filter(|(number, _)| *number >= block_to_unwind)
+        {
+            amm_addresses.push(*amm_address);
+            self.created_amms.pop_front();
         }
 
-        cache.push_front(state_change)
+        amm_addresses
+    }
+
+    /// Returns the recorded hash for the block number, if it is within the block history
+    pub fn block_hash(&self, block_number: u64) -> Option<B256> {
+        self.block_hashes
+            .iter()
+            .find(|(number, _)| *number == block_number)
+            .map(|(_, hash)| *hash)
+    }
+
+    /// Returns the oldest and latest block numbers in the block history
+    pub fn block_hash_bounds(&self) -> Option<(u64, u64)> {
+        let (oldest, _) = self.block_hashes.back()?;
+        let (latest, _) = self.block_hashes.front()?;
+        Some((*oldest, *latest))
     }
 
     /// Unwinds the state changes up to the given block number
     /// Returns the state of the affected AMMs at the block number provided
-    pub fn unwind_state_changes(&mut self, block_to_unwind: u64) -> Vec<AMM> {
-        let cache = &mut self.cache;
-
+    ///
+    /// Returns `StateSpaceError::ReorgTooDeep` if the block to unwind is older than the oldest block in the cache,
+    /// in which case the cache is left untouched.
+    pub fn unwind_state_changes(
+        &mut self,
+        block_to_unwind: u64,
+    ) -> Result<Vec<AMM>, StateSpaceError> {
         if block_to_unwind < self.oldest_block {
-            panic!("Block to unwind < oldest block in cache");
+            return Err(StateSpaceError::ReorgTooDeep(
+                block_to_unwind,
+                self.oldest_block,
+            ));
         }
 
+        // Block hashes at or above the block to unwind are no longer canonical
+        while self
+            .block_hashes
+            .front()
+            .is_some_and(|(number, _)| *number >= block_to_unwind)
+        {
+            self.block_hashes.pop_front();
+        }
+
+        let cache = &mut self.cache;
+
         // If the block to unwind is greater than the latest state change in the block, exit early
         if cache
             .front()
-            .map_or(true, |latest| block_to_unwind > latest.block_number)
+            .is_none_or(|latest| block_to_unwind > latest.block_number)
         {
-            return vec![];
+            return Ok(vec![]);
         }
 
         let pivot_idx = cache
@@ -65,7 +216,55 @@ impl StateChangeCache {
             cache.drain(..).collect::<Vec<StateChange>>()
         };
 
-        self.flatten_state_changes(state_changes)
+        Ok(self.flatten_state_changes(state_changes))
+    }
+
+    /// Unwinds the state changes of a single AMM at or above the given block number, leaving other AMMs untouched
+    /// Returns the state of the AMM before `block_to_unwind`, or `None` if the AMM has no state changes to unwind
+    pub fn unwind_amm_state_changes(
+        &mut self,
+        amm_address: Address,
+        block_to_unwind: u64,
+    ) -> Result<Option<AMM>, StateSpaceError> {
+        if block_to_unwind < self.oldest_block {
+            return Err(StateSpaceError::ReorgTooDeep(
+                block_to_unwind,
+                self.oldest_block,
+            ));
+        }
+
+        // State changes are ordered newest first, so the last state found is the oldest
+        let mut prev_state = None;
+        for state_change in self
+            .cache
+            .iter_mut()
+            .take_while(|state_change| state_change.block_number >= block_to_unwind)
+        {
+            if let Some(amm) = state_change
+                .state_change
+                .iter()
+                .find(|amm| amm.address() == amm_address)
+                .cloned()
+            {
+                state_change
+                    .state_change
+                    .retain(|amm| amm.address() != amm_address);
+                prev_state = Some(amm);
+            }
+        }
+
+        self.cache
+            .retain(|state_change| !state_change.state_change.is_empty());
+
+        Ok(prev_state)
+    }
+
+    /// Clears all state changes and block hashes from the cache
+    pub fn clear(&mut self) {
+        self.oldest_block = 0;
+        self.cache.clear();
+        self.block_hashes.clear();
+        self.created_amms.clear();
     }
 
     fn flatten_state_changes(&self, state_changes: Vec<StateChange>) -> Vec<AMM> {
@@ -83,4 +282,181 @@ impl StateChangeCache {
     }
 }
 
-// TODO: add tests
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::{address, Address, B256};
+
+    use super::StateChangeCache;
+    use crate::{
+        amm::{uniswap_v2::UniswapV2Pool, AMM},
+        state_space::{error::StateSpaceError, StateChange},
+    };
+
+    const POOL_ADDRESS: Address = address!("B4e16d0168e52d35CaCD2c6185b44281Ec28C9Dc");
+
+    fn pool_with_reserve(reserve_0: u128) -> AMM {
+        AMM::UniswapV2Pool(UniswapV2Pool {
+            address: POOL_ADDRESS,
+            reserve_0,
+            ..Default::default()
+        })
+    }
+
+    fn reserve_0(amm: &AMM) -> u128 {
+        match amm {
+            AMM::UniswapV2Pool(pool) => pool.reserve_0,
+            _ => panic!("Unexpected AMM variant"),
+        }
+    }
+
+    #[test]
+    fn test_unwind_state_changes() {
+        let mut cache = StateChangeCache::new();
+
+        // The cached state for each block is the state of the pool before the block was applied
+        for block_number in 1..=5 {
+            cache
+                .add_state_change_to_cache(StateChange::new(
+                    vec![pool_with_reserve(block_number as u128)],
+                    block_number,
+                    B256::ZERO,
+                ))
+                .unwrap();
+        }
+
+        let amms = cache.unwind_state_changes(3).unwrap();
+
+        assert_eq!(amms.len(), 1);
+        assert_eq!(reserve_0(&amms[0]), 3);
+        assert!(cache.unwind_state_changes(3).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_unwind_state_changes_reorg_too_deep() {
+        let mut cache = StateChangeCache::with_depth(2);
+
+        for block_number in 1..=4 {
+            cache
+                .add_state_change_to_cache(StateChange::new(
+                    vec![pool_with_reserve(block_number as u128)],
+                    block_number,
+                    B256::ZERO,
+                ))
+                .unwrap();
+        }
+
+        assert!(matches!(
+            cache.unwind_state_changes(1),
+            Err(StateSpaceError::ReorgTooDeep(1, 3))
+        ));
+
+        // The cache is left untouched after a failed unwind
+        let amms = cache.unwind_state_changes(3).unwrap();
+        assert_eq!(reserve_0(&amms[0]), 3);
+    }
+
+    #[test]
+    fn test_unwind_amm_state_changes() {
+        let other_pool = AMM::UniswapV2Pool(UniswapV2Pool {
+            address: address!("A478c2975Ab1Ea89e8196811F51A7B7Ade33eB11"),
+            ..Default::default()
+        });
+
+        let mut cache = StateChangeCache::new();
+        for block_number in 1..=3 {
+            cache
+                .add_state_change_to_cache(StateChange::new(
+                    vec![pool_with_reserve(block_number as u128), other_pool.clone()],
+                    block_number,
+                    B256::ZERO,
+                ))
+                .unwrap();
+        }
+
+        let amm = cache
+            .unwind_amm_state_changes(POOL_ADDRESS, 2)
+            .unwrap()
+            .unwrap();
+        assert_eq!(reserve_0(&amm), 2);
+
+        // The pool has no state changes left to unwind at or above block 2
+        assert!(cache
+            .unwind_amm_state_changes(POOL_ADDRESS, 2)
+            .unwrap()
+            .is_none());
+
+        // The other pool is unaffected by the targeted unwind
+        assert_eq!(cache.unwind_state_changes(1).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_add_state_change_out_of_order() {
+        let mut cache = StateChangeCache::with_depth(0);
+        assert_eq!(cache.depth(), 1);
+
+        cache
+            .add_state_change_to_cache(StateChange::new(vec![pool_with_reserve(2)], 2, B256::ZERO))
+            .unwrap();
+
+        assert!(matches!(
+            cache.add_state_change_to_cache(StateChange::new(
+                vec![pool_with_reserve(1)],
+                1,
+                B256::ZERO
+            )),
+            Err(StateSpaceError::StateChangeOutOfOrder(1, 2))
+        ));
+
+        // State changes at or above the latest block are accepted, evicting the oldest at depth
+        cache
+            .add_state_change_to_cache(StateChange::new(vec![pool_with_reserve(3)], 3, B256::ZERO))
+            .unwrap();
+        assert!(matches!(
+            cache.unwind_state_changes(2),
+            Err(StateSpaceError::ReorgTooDeep(2, 3))
+        ));
+    }
+
+    #[test]
+    fn test_unwind_created_amms() {
+        let mut cache = StateChangeCache::with_depth(2);
+
+        cache.add_created_amm(1, Address::with_last_byte(1));
+        cache.add_created_amm(4, Address::with_last_byte(4));
+        cache.add_created_amm(5, Address::with_last_byte(5));
+
+        // Creations older than the cache depth are discarded
+        assert_eq!(cache.created_amms.len(), 2);
+
+        assert_eq!(
+            cache.unwind_created_amms(5),
+            vec![Address::with_last_byte(5)]
+        );
+        assert!(cache.unwind_created_amms(5).is_empty());
+        assert_eq!(
+            cache.unwind_created_amms(1),
+            vec![Address::with_last_byte(4)]
+        );
+    }
+
+    #[test]
+    fn test_block_hashes() {
+        let mut cache = StateChangeCache::with_depth(3);
+
+        for block_number in 1..=4 {
+            cache.add_block_hash(block_number, B256::with_last_byte(block_number as u8));
+        }
+
+        assert_eq!(cache.block_hash_bounds(), Some((2, 4)));
+        assert_eq!(cache.block_hash(1), None);
+        assert_eq!(cache.block_hash(3), Some(B256::with_last_byte(3)));
+
+        // Adding a block at an existing height replaces the history at and above it
+        cache.add_block_hash(3, B256::with_last_byte(30));
+        assert_eq!(cache.block_hash_bounds(), Some((2, 3)));
+        assert_eq!(cache.block_hash(3), Some(B256::with_last_byte(30)));
+
+        cache.unwind_state_changes(3).unwrap();
+        assert_eq!(cache.block_hash_bounds(), Some((2, 2)));
+    }
+}
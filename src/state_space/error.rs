@@ -1,7 +1,11 @@
-use alloy::{primitives::Address, rpc::types::eth::Block, transports::TransportError};
+use alloy::{primitives::Address, transports::TransportError};
 use thiserror::Error;
 
-use crate::errors::{AMMError, ArithmeticError, EventLogError};
+use super::{
+    event::StateSpaceEvent, journal::BlockHeader, multi_chain::ChainEvent,
+    verifier::VerificationReport,
+};
+use crate::errors::{AMMError, ArithmeticError, EventLogError, SwapSimulationError};
 
 #[derive(Error, Debug)]
 pub enum StateSpaceError {
@@ -26,11 +30,33 @@ pub enum StateSpaceError {
     #[error("Block number not found")]
     BlockNumberNotFound,
     #[error(transparent)]
-    StateChangeSendError(#[from] tokio::sync::mpsc::error::SendError<Vec<Address>>),
+    StateChangeSendError(#[from] tokio::sync::mpsc::error::SendError<StateSpaceEvent>),
     #[error(transparent)]
-    BlockSendError(#[from] tokio::sync::mpsc::error::SendError<Block>),
+    ChainEventSendError(#[from] tokio::sync::mpsc::error::SendError<ChainEvent>),
+    #[error(transparent)]
+    VerificationReportSendError(#[from] tokio::sync::mpsc::error::SendError<VerificationReport>),
+    #[error(transparent)]
+    BlockSendError(#[from] tokio::sync::mpsc::error::SendError<BlockHeader>),
     #[error("Already listening for state changes")]
     AlreadyListeningForStateChanges,
+    #[error("Reorg at block {0} is deeper than the oldest block in the state change cache {1}")]
+    ReorgTooDeep(u64, u64),
+    #[error("State change at block {0} is older than the latest cached state change at block {1}")]
+    StateChangeOutOfOrder(u64, u64),
+    #[error("Provider does not support pubsub subscriptions")]
+    PubsubUnavailable,
     #[error(transparent)]
     JoinError(#[from] tokio::task::JoinError),
+    #[error(transparent)]
+    SwapSimulationError(#[from] SwapSimulationError),
+    #[error(transparent)]
+    IOError(#[from] std::io::Error),
+    #[error(transparent)]
+    SerdeJsonError(#[from] serde_json::Error),
+    #[error("AMM {0} not found in state space")]
+    AmmNotFound(Address),
+    #[error("AMMs {0:?} changed in the base store after being copied into the overlay")]
+    OverlayConflict(Vec<Address>),
+    #[error("Overlay is not based on the state space of the manager")]
+    OverlayBaseMismatch,
 }
@@ -0,0 +1,149 @@
+use alloy::{
+    primitives::{Address, B256},
+    rpc::types::eth::Log,
+};
+
+use crate::amm::{AutomatedMarketMaker, AMM};
+
+/// Events emitted to state space subscribers
+#[derive(Debug, Clone)]
+pub enum StateSpaceEvent {
+    /// State changes applied from the logs of a single block
+    StateChanged(StateChangeEvent),
+    /// AMMs restored to their state before `block_number` after a reorg
+    Unwound {
+        /// The first block that was unwound
+        block_number: u64,
+        changes: Vec<AmmStateChange>,
+    },
+    /// The AMMs affected by a reorg deeper than the state change cache were repopulated at the block
+    Resynced {
+        block_number: u64,
+        block_hash: B256,
+        changes: Vec<AmmStateChange>,
+    },
+    /// AMMs created by a tracked factory and added to the state space
+    AmmsCreated(Vec<AMM>),
+    /// AMMs created by a tracked factory in blocks that were unwound after a reorg, and removed from the state space
+    AmmsRemoved(Vec<AMM>),
+    /// Changes merged per AMM for subscribers with `SubscriberPolicy::Coalesce`, one entry per AMM
+    Coalesced(Vec<AmmStateChange>),
+}
+
+impl StateSpaceEvent {
+    /// Returns the addresses of the AMMs affected by the event
+    pub fn amm_addresses(&self) -> Vec<Address> {
+        match self {
+            StateSpaceEvent::StateChanged(event) => event.amm_addresses(),
+            StateSpaceEvent::Unwound { changes, .. }
+            | StateSpaceEvent::Resynced { changes, .. }
+            | StateSpaceEvent::Coalesced(changes) => {
+                changes.iter().map(AmmStateChange::address).collect()
+            }
+            StateSpaceEvent::AmmsCreated(amms) | StateSpaceEvent::AmmsRemoved(amms) => {
+                amms.iter().map(|amm| amm.address()).collect()
+            }
+        }
+    }
+}
+
+/// The state of an AMM before and after a change
+#[derive(Debug, Clone)]
+pub struct AmmStateChange {
+    pub prev: AMM,
+    pub new: AMM,
+}
+
+impl AmmStateChange {
+    pub fn new(prev: AMM, new: AMM) -> Self {
+        AmmStateChange { prev, new }
+    }
+
+    pub fn address(&self) -> Address {
+        self.new.address()
+    }
+}
+
+/// State changes applied from the logs of a single block
+#[derive(Debug, Clone)]
+pub struct StateChangeEvent {
+    pub block_number: u64,
+    pub block_hash: B256,
+    /// Logs that triggered the state changes, in the order they were applied
+    pub logs: Vec<Log>,
+    /// State of each affected AMM before and after the block, one entry per AMM
+    pub changes: Vec<AmmStateChange>,
+}
+
+impl StateChangeEvent {
+    pub fn new(block_number: u64, block_hash: B256) -> Self {
+        StateChangeEvent {
+            block_number,
+            block_hash,
+            logs: vec![],
+            changes: vec![],
+        }
+    }
+
+    /// Records a state change triggered by the log.
+    /// If the AMM has already changed in this block, its previous state is kept and only the new state is updated.
+    pub fn record_change(&mut self, log: Log, prev: AMM, new: AMM) {
+        self.logs.push(log);
+
+        if let Some(change) = self
+            .changes
+            .iter_mut()
+            .find(|change| change.address() == new.address())
+        {
+            change.new = new;
+        } else {
+            self.changes.push(AmmStateChange::new(prev, new));
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    pub fn amm_addresses(&self) -> Vec<Address> {
+        self.changes.iter().map(AmmStateChange::address).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        primitives::{address, B256},
+        rpc::types::eth::Log,
+    };
+
+    use super::StateChangeEvent;
+    use crate::amm::{uniswap_v2::UniswapV2Pool, AMM};
+
+    fn pool_with_reserve(reserve_0: u128) -> AMM {
+        AMM::UniswapV2Pool(UniswapV2Pool {
+            address: address!("B4e16d0168e52d35CaCD2c6185b44281Ec28C9Dc"),
+            reserve_0,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_record_change_keeps_state_before_block() {
+        let mut event = StateChangeEvent::new(1, B256::ZERO);
+
+        event.record_change(Log::default(), pool_with_reserve(1), pool_with_reserve(2));
+        event.record_change(Log::default(), pool_with_reserve(2), pool_with_reserve(3));
+
+        assert_eq!(event.logs.len(), 2);
+        assert_eq!(event.changes.len(), 1);
+
+        let (AMM::UniswapV2Pool(prev), AMM::UniswapV2Pool(new)) =
+            (&event.changes[0].prev, &event.changes[0].new)
+        else {
+            panic!("Unexpected AMM variant");
+        };
+        assert_eq!(prev.reserve_0, 1);
+        assert_eq!(new.reserve_0, 3);
+    }
+}
@@ -0,0 +1,215 @@
+use std::{collections::HashSet, sync::Arc};
+
+use alloy::{
+    network::Network,
+    primitives::{Address, B256},
+    providers::Provider,
+    rpc::types::eth::{Filter, Log},
+};
+use futures::future::try_join_all;
+
+use super::{error::StateSpaceError, store::StateSpaceStore};
+use crate::amm::{
+    factory::{AutomatedMarketMakerFactory, Factory},
+    AutomatedMarketMaker,
+};
+
+/// Default maximum number of addresses in a single address scoped filter
+pub const DEFAULT_MAX_FILTER_ADDRESSES: usize = 1000;
+
+/// Determines how logs are fetched for the AMMs in the state space
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFilterMode {
+    /// Filters logs by event signatures only, returning matching logs from every contract on the chain
+    #[default]
+    EventSignatures,
+    /// Filters logs by event signatures and the AMM addresses in the state space.
+    /// Addresses are split across multiple filters containing at most `max_addresses` each.
+    Addresses { max_addresses: usize },
+}
+
+impl LogFilterMode {
+    /// Address scoped filter mode using `DEFAULT_MAX_FILTER_ADDRESSES`
+    pub fn addresses() -> Self {
+        LogFilterMode::Addresses {
+            max_addresses: DEFAULT_MAX_FILTER_ADDRESSES,
+        }
+    }
+
+    /// Builds the filters used to fetch logs for the given event signatures and AMM addresses
+    pub fn filters(&self, event_signatures: Vec<B256>, addresses: &[Address]) -> Vec<Filter> {
+        match self {
+            LogFilterMode::EventSignatures => vec![Filter::new().event_signature(event_signatures)],
+            LogFilterMode::Addresses { max_addresses } => addresses
+                .chunks((*max_addresses).max(1))
+                .map(|chunk| {
+                    Filter::new()
+                        .event_signature(event_signatures.clone())
+                        .address(chunk.to_vec())
+                })
+                .collect(),
+        }
+    }
+
+    /// Builds the filters used to fetch logs for every AMM in the state space
+    pub fn state_space_filters<S: StateSpaceStore>(&self, state: &S) -> Vec<Filter> {
+        let mut event_signatures = HashSet::new();
+        let mut addresses = vec![];
+        state.for_each(|amm| {
+            event_signatures.extend(amm.sync_on_event_signatures());
+            addresses.push(amm.address());
+        });
+
+        self.filters(event_signatures.into_iter().collect(), &addresses)
+    }
+}
+
+/// Log filters for the state space along with the configuration used to build them.
+///
+/// This is shared between the state space manager and running subscriptions so that changes to the tracked AMMs
+/// or factories are reflected in the next block without restarting the subscription.
+#[derive(Debug, Default)]
+pub struct StateSpaceFilters {
+    mode: LogFilterMode,
+    factories: Vec<Factory>,
+    filters: Vec<Filter>,
+}
+
+impl StateSpaceFilters {
+    pub fn new<S: StateSpaceStore>(
+        mode: LogFilterMode,
+        factories: Vec<Factory>,
+        state: &S,
+    ) -> Self {
+        let mut state_space_filters = StateSpaceFilters {
+            mode,
+            factories,
+            filters: vec![],
+        };
+
+        state_space_filters.refresh(state);
+        state_space_filters
+    }
+
+    pub fn mode(&self) -> LogFilterMode {
+        self.mode
+    }
+
+    pub fn factories(&self) -> &[Factory] {
+        &self.factories
+    }
+
+    pub fn filters(&self) -> &[Filter] {
+        &self.filters
+    }
+
+    pub fn set_mode<S: StateSpaceStore>(&mut self, mode: LogFilterMode, state: &S) {
+        self.mode = mode;
+        self.refresh(state);
+    }
+
+    pub fn set_factories<S: StateSpaceStore>(&mut self, factories: Vec<Factory>, state: &S) {
+        self.factories = factories;
+        self.refresh(state);
+    }
+
+    /// Rebuilds the filters from the AMMs in the state space and the tracked factories
+    pub fn refresh<S: StateSpaceStore>(&mut self, state: &S) {
+        let mut filters = self.mode.state_space_filters(state);
+
+        // Watch for new AMMs created by the tracked factories
+        if !self.factories.is_empty() {
+            let amm_created_event_signatures = self
+                .factories
+                .iter()
+                .map(|factory| factory.amm_created_event_signature())
+                .collect::<HashSet<B256>>()
+                .into_iter()
+                .collect::<Vec<B256>>();
+
+            let factory_addresses = self
+                .factories
+                .iter()
+                .map(|factory| factory.address())
+                .collect::<Vec<Address>>();
+
+            filters.push(
+                Filter::new()
+                    .event_signature(amm_created_event_signatures)
+                    .address(factory_addresses),
+            );
+        }
+
+        self.filters = filters;
+    }
+
+    /// Returns the tracked factory that emitted the log if it is an AMM created event
+    pub fn factory_for_log(&self, log: &Log) -> Option<&Factory> {
+        let event_signature = log.topics().first()?;
+
+        self.factories.iter().find(|factory| {
+            factory.address() == log.address()
+                && factory.amm_created_event_signature() == *event_signature
+        })
+    }
+}
+
+/// Gets the logs matching any of the filters within the block range.
+///
+/// Returns the logs ordered by block number and log index.
+pub async fn get_logs_for_filters<N, P>(
+    filters: &[Filter],
+    from_block: u64,
+    to_block: u64,
+    provider: Arc<P>,
+) -> Result<Vec<Log>, StateSpaceError>
+where
+    N: Network,
+    P: Provider<N>,
+{
+    let requests = filters.iter().map(|filter| {
+        let filter = filter.clone().from_block(from_block).to_block(to_block);
+        let provider = provider.clone();
+        async move { provider.get_logs(&filter).await }
+    });
+
+    let mut logs = try_join_all(requests)
+        .await?
+        .into_iter()
+        .flatten()
+        .collect::<Vec<Log>>();
+
+    // Logs from separate filters need to be merged back into chain order
+    if filters.len() > 1 {
+        logs.sort_by_key(|log| (log.block_number, log.log_index));
+    }
+
+    Ok(logs)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::{Address, B256};
+
+    use super::LogFilterMode;
+
+    #[test]
+    fn test_address_filters_are_chunked() {
+        let addresses = (0..5)
+            .map(Address::with_last_byte)
+            .collect::<Vec<Address>>();
+        let event_signatures = vec![B256::with_last_byte(1)];
+
+        let filters = LogFilterMode::Addresses { max_addresses: 2 }
+            .filters(event_signatures.clone(), &addresses);
+
+        assert_eq!(filters.len(), 3);
+        assert_eq!(filters[0].address.len(), 2);
+        assert_eq!(filters[2].address.len(), 1);
+
+        let filters = LogFilterMode::EventSignatures.filters(event_signatures, &addresses);
+
+        assert_eq!(filters.len(), 1);
+        assert!(filters[0].address.is_empty());
+    }
+}
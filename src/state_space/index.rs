@@ -0,0 +1,146 @@
+use alloy::primitives::Address;
+use im::{HashMap, HashSet};
+
+use crate::amm::{AutomatedMarketMaker, AMM};
+
+/// Secondary indices from tokens and unordered token pairs to the AMMs that contain them.
+///
+/// Backed by persistent maps so that clones are cheap and share structure with the original.
+#[derive(Debug, Clone, Default)]
+pub struct TokenIndex {
+    tokens: HashMap<Address, HashSet<Address>>,
+    pairs: HashMap<(Address, Address), HashSet<Address>>,
+}
+
+impl TokenIndex {
+    pub fn new() -> Self {
+        TokenIndex::default()
+    }
+
+    pub fn insert(&mut self, amm: &AMM) {
+        let amm_address = amm.address();
+        let tokens = amm.tokens();
+
+        for (i, token) in tokens.iter().enumerate() {
+            self.tokens.entry(*token).or_default().insert(amm_address);
+
+            for other_token in tokens.iter().skip(i + 1) {
+                self.pairs
+                    .entry(pair_key(*token, *other_token))
+                    .or_default()
+                    .insert(amm_address);
+            }
+        }
+    }
+
+    pub fn remove(&mut self, amm: &AMM) {
+        let amm_address = amm.address();
+        let tokens = amm.tokens();
+
+        for (i, token) in tokens.iter().enumerate() {
+            remove_from_index(&mut self.tokens, *token, amm_address);
+
+            for other_token in tokens.iter().skip(i + 1) {
+                remove_from_index(&mut self.pairs, pair_key(*token, *other_token), amm_address);
+            }
+        }
+    }
+
+    /// Returns the addresses of the AMMs containing the token
+    pub fn amms_containing(&self, token: &Address) -> Vec<Address> {
+        self.tokens
+            .get(token)
+            .map(|amms| amms.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns the addresses of the AMMs containing both tokens, regardless of order
+    pub fn amms_for_pair(&self, token_a: &Address, token_b: &Address) -> Vec<Address> {
+        self.pairs
+            .get(&pair_key(*token_a, *token_b))
+            .map(|amms| amms.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn clear(&mut self) {
+        self.tokens.clear();
+        self.pairs.clear();
+    }
+}
+
+impl FromIterator<AMM> for TokenIndex {
+    fn from_iter<T: IntoIterator<Item = AMM>>(amms: T) -> Self {
+        let mut index = TokenIndex::new();
+        for amm in amms {
+            index.insert(&amm);
+        }
+        index
+    }
+}
+
+/// Orders the pair so that lookups are independent of token order
+fn pair_key(token_a: Address, token_b: Address) -> (Address, Address) {
+    if token_a <= token_b {
+        (token_a, token_b)
+    } else {
+        (token_b, token_a)
+    }
+}
+
+fn remove_from_index<K: Clone + Eq + std::hash::Hash>(
+    index: &mut HashMap<K, HashSet<Address>>,
+    key: K,
+    amm_address: Address,
+) {
+    if let Some(amms) = index.get_mut(&key) {
+        amms.remove(&amm_address);
+        if amms.is_empty() {
+            index.remove(&key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::Address;
+
+    use super::TokenIndex;
+    use crate::amm::{uniswap_v2::UniswapV2Pool, AMM};
+
+    fn pool(address: u8, token_a: u8, token_b: u8) -> AMM {
+        AMM::UniswapV2Pool(UniswapV2Pool {
+            address: Address::with_last_byte(address),
+            token_a: Address::with_last_byte(token_a),
+            token_b: Address::with_last_byte(token_b),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_token_index() {
+        let mut index = [pool(1, 10, 11), pool(2, 11, 10), pool(3, 10, 12)]
+            .into_iter()
+            .collect::<TokenIndex>();
+
+        let token_a = Address::with_last_byte(10);
+        let token_b = Address::with_last_byte(11);
+
+        let mut pair_amms = index.amms_for_pair(&token_b, &token_a);
+        pair_amms.sort();
+        assert_eq!(
+            pair_amms,
+            vec![Address::with_last_byte(1), Address::with_last_byte(2)]
+        );
+        assert_eq!(index.amms_containing(&token_a).len(), 3);
+
+        index.remove(&pool(1, 10, 11));
+        assert_eq!(
+            index.amms_for_pair(&token_a, &token_b),
+            vec![Address::with_last_byte(2)]
+        );
+
+        index.remove(&pool(2, 11, 10));
+        assert!(index.amms_for_pair(&token_a, &token_b).is_empty());
+        assert!(index.amms_containing(&token_b).is_empty());
+    }
+}
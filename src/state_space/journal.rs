@@ -0,0 +1,158 @@
+use std::{
+    fmt,
+    fs::File,
+    io::{BufRead, BufReader},
+    path::Path,
+};
+
+use alloy::{network::primitives::HeaderResponse, primitives::B256, rpc::types::eth::Log};
+use serde::{
+    de::{self, value::MapAccessDeserializer, MapAccess, Visitor},
+    Deserialize, Deserializer, Serialize,
+};
+
+use super::error::StateSpaceError;
+use crate::amm::AMM;
+
+/// The fields of a block header used to sync the state space and detect reorgs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockHeader {
+    pub number: u64,
+    pub hash: B256,
+    pub parent_hash: B256,
+}
+
+impl BlockHeader {
+    /// Reads the fields used by the state space from a header returned by the provider
+    pub fn from_header<H: HeaderResponse>(header: &H) -> Self {
+        BlockHeader {
+            number: header.number(),
+            hash: header.hash(),
+            parent_hash: header.parent_hash(),
+        }
+    }
+}
+
+/// A record of the input received by the state space.
+///
+/// Journals are stored as JSON lines, with one record per line tagged by its `type`:
+///
+/// ```text
+/// {"type":"header","number":2,"hash":"0x..","parent_hash":"0x.."}
+/// {"type":"ancestor","number":1,"hash":"0x..","parent_hash":"0x.."}
+/// {"type":"amm_created","block_number":2,"amm":{..}}
+/// {"type":"logs","from_block":1,"to_block":2,"logs":[..]}
+/// ```
+///
+/// Each `header` is followed by the `ancestor` headers fetched while walking back to find its common ancestor with the synced chain,
+/// then by an `amm_created` record for each AMM created by a tracked factory, and finally by the `logs` applied for the blocks it made canonical.
+/// The logs include those fetched for the created AMMs, and exclude the AMM created logs themselves.
+/// A `header` without `logs` was either already synced or required the state space to be resynced.
+///
+/// Journals recorded by a `StateSpaceManager` are written by `recorder::JournalRecorder`, which rotates between numbered files.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum JournalRecord {
+    /// A new chain head received by the state space
+    Header(BlockHeader),
+    /// A parent header fetched while walking back from a chain head to find its common ancestor
+    Ancestor(BlockHeader),
+    /// An AMM created by a tracked factory, populated as of the end of its creation block
+    AmmCreated { block_number: u64, amm: Box<AMM> },
+    /// Logs applied for the block range following a chain head
+    Logs {
+        from_block: u64,
+        to_block: u64,
+        logs: Vec<Log>,
+    },
+}
+
+/// The fields following the tag of a `JournalRecord::AmmCreated`
+#[derive(Deserialize)]
+struct AmmCreatedFields {
+    block_number: u64,
+    amm: Box<AMM>,
+}
+
+/// The fields following the tag of a `JournalRecord::Logs`
+#[derive(Deserialize)]
+struct LogsFields {
+    from_block: u64,
+    to_block: u64,
+    logs: Vec<Log>,
+}
+
+/// The `type` tag of a `JournalRecord`
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum RecordType {
+    Header,
+    Ancestor,
+    AmmCreated,
+    Logs,
+}
+
+/// Records are deserialized without buffering, unlike the derived implementation for an internally tagged enum,
+/// since buffered content can not hold the `u128` fields of AMMs. The `type` tag must be the first field, as it is when serialized.
+impl<'de> Deserialize<'de> for JournalRecord {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(JournalRecordVisitor)
+    }
+}
+
+struct JournalRecordVisitor;
+
+impl<'de> Visitor<'de> for JournalRecordVisitor {
+    type Value = JournalRecord;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a journal record starting with its type")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        if map.next_key::<String>()?.as_deref() != Some("type") {
+            return Err(de::Error::missing_field("type"));
+        }
+
+        let record_type = map.next_value::<RecordType>()?;
+        let fields = MapAccessDeserializer::new(map);
+
+        Ok(match record_type {
+            RecordType::Header => JournalRecord::Header(BlockHeader::deserialize(fields)?),
+            RecordType::Ancestor => JournalRecord::Ancestor(BlockHeader::deserialize(fields)?),
+            RecordType::AmmCreated => {
+                let AmmCreatedFields { block_number, amm } = AmmCreatedFields::deserialize(fields)?;
+                JournalRecord::AmmCreated { block_number, amm }
+            }
+            RecordType::Logs => {
+                let LogsFields {
+                    from_block,
+                    to_block,
+                    logs,
+                } = LogsFields::deserialize(fields)?;
+                JournalRecord::Logs {
+                    from_block,
+                    to_block,
+                    logs,
+                }
+            }
+        })
+    }
+}
+
+/// Reads every record from a JSON lines journal, skipping empty lines
+pub fn read_journal<P: AsRef<Path>>(path: P) -> Result<Vec<JournalRecord>, StateSpaceError> {
+    let reader = BufReader::new(File::open(path)?);
+
+    let mut records = vec![];
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        records.push(serde_json::from_str(&line)?);
+    }
+
+    Ok(records)
+}
@@ -1,38 +1,73 @@
 pub mod cache;
-#[cfg(feature = "artemis")]
 pub mod collector;
 pub mod error;
+pub mod event;
+pub mod filter;
+pub mod index;
+pub mod journal;
+pub mod multi_chain;
+pub mod overlay;
+pub mod recorder;
+pub mod replay;
+pub mod snapshot;
+pub mod store;
+pub mod subscriber;
+pub mod verifier;
 
 use std::{
     collections::{HashMap, HashSet},
+    future::Future,
     marker::PhantomData,
     ops::{Deref, DerefMut},
     sync::Arc,
+    time::Duration,
 };
 
 use alloy::{
-    network::Network,
-    primitives::{Address, FixedBytes},
+    network::{BlockResponse, Network},
+    primitives::{Address, B256},
     providers::Provider,
-    rpc::types::eth::{Block, Filter, Log},
+    pubsub::Subscription,
+    rpc::types::eth::{BlockNumberOrTag, Filter, Log},
+    transports::{RpcError, TransportErrorKind},
 };
-use cache::StateChangeCache;
+use cache::{StateChangeCache, DEFAULT_CACHE_DEPTH};
 use error::StateSpaceError;
+use event::{AmmStateChange, StateChangeEvent, StateSpaceEvent};
+use filter::{get_logs_for_filters, LogFilterMode, StateSpaceFilters};
+use futures::StreamExt;
+use journal::{BlockHeader, JournalRecord};
+use overlay::{same_state, StateSpaceOverlay};
+use recorder::{JournalRecorder, JournalWriter};
+use replay::StateSpaceReplay;
+use snapshot::{StateSpaceSnapshot, StateSpaceSnapshots};
+use store::{LockedStateSpace, StateSpaceStore};
+use subscriber::{StateSpaceBroadcaster, StateSpaceSubscriber, SubscriberPolicy};
 use tokio::{
     sync::{
         mpsc::{Receiver, Sender},
         RwLock,
     },
     task::JoinHandle,
+    time::MissedTickBehavior,
 };
 
 use crate::{
-    amm::{AutomatedMarketMaker, AMM},
+    amm::{
+        factory::{AutomatedMarketMakerFactory, Factory},
+        uniswap_v3::UniswapV3Pool,
+        AutomatedMarketMaker, AMM,
+    },
     errors::EventLogError,
+    sync::{checkpoint::sort_amms, populate_amms, populate_tick_data},
 };
 
-// TODO: bench this with a dashmap
-#[derive(Debug)]
+/// Maximum number of consecutive attempts to resubscribe to or poll for new blocks before giving up
+const MAX_RESUBSCRIBE_ATTEMPTS: u32 = 5;
+/// Backoff between resubscription or polling attempts, multiplied by the attempt number
+const RESUBSCRIBE_BACKOFF: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Default)]
 pub struct StateSpace(pub HashMap<Address, AMM>);
 
 impl StateSpace {
@@ -63,53 +98,259 @@ impl From<Vec<AMM>> for StateSpace {
 }
 
 #[derive(Debug)]
-pub struct StateSpaceManager<N, P> {
-    state: Arc<RwLock<StateSpace>>,
+pub struct StateSpaceManager<N, P, S = LockedStateSpace> {
+    state: Arc<S>,
     state_change_cache: Arc<RwLock<StateChangeCache>>,
+    /// Log filters shared with running subscriptions, refreshed whenever the tracked AMMs or factories change
+    filters: Arc<RwLock<StateSpaceFilters>>,
+    /// Snapshots of the state space published after each block is fully applied
+    snapshots: StateSpaceSnapshots,
+    /// Optional journal of every block header, created AMM and log batch received by subscriptions
+    recorder: Option<JournalWriter>,
+    /// Broadcaster of the latest subscription, or of the next subscription if it has not been claimed yet
+    broadcaster: std::sync::Mutex<Arc<StateSpaceBroadcaster>>,
     provider: Arc<P>,
     phantom: PhantomData<N>,
 }
 
-// TODO: Much of this can be simplified
 impl<N, P> StateSpaceManager<N, P>
 where
-    N: Network<HeaderResponse = Block, BlockResponse = Block>,
+    N: Network,
     P: Provider<N> + 'static,
 {
     pub fn new(amms: Vec<AMM>, provider: Arc<P>) -> Self {
+        Self::with_cache_depth(amms, DEFAULT_CACHE_DEPTH, provider)
+    }
+
+    /// Creates a new state space manager that retains `cache_depth` blocks of state changes for unwinding reorgs.
+    ///
+    /// See `cache::cache_depth_for_chain` for per chain presets.
+    pub fn with_cache_depth(amms: Vec<AMM>, cache_depth: usize, provider: Arc<P>) -> Self {
+        Self::with_store(LockedStateSpace::from(amms), cache_depth, provider)
+    }
+}
+
+// TODO: Much of this can be simplified
+impl<N, P, S> StateSpaceManager<N, P, S>
+where
+    N: Network,
+    P: Provider<N> + 'static,
+    S: StateSpaceStore + 'static,
+{
+    /// Creates a new state space manager backed by the given store
+    pub fn with_store(store: S, cache_depth: usize, provider: Arc<P>) -> Self {
+        let filters = StateSpaceFilters::new(LogFilterMode::default(), vec![], &store);
+        let snapshot = StateSpaceSnapshot::new(0, store.amms());
+
         Self {
-            state: Arc::new(RwLock::new(amms.into())),
-            state_change_cache: Arc::new(RwLock::new(StateChangeCache::new())),
+            state: Arc::new(store),
+            state_change_cache: Arc::new(RwLock::new(StateChangeCache::with_depth(cache_depth))),
+            filters: Arc::new(RwLock::new(filters)),
+            snapshots: StateSpaceSnapshots::new(snapshot),
+            recorder: None,
+            broadcaster: std::sync::Mutex::new(Arc::new(StateSpaceBroadcaster::new())),
             provider,
             phantom: PhantomData,
         }
     }
 
-    pub async fn filter(&self) -> Filter {
-        let event_signatures = self
-            .state
-            .read()
+    /// Records every block header, created AMM and log batch received by subscriptions to the journal, so that the input can be replayed later.
+    ///
+    /// Records are written on a dedicated thread. If writing fails, the error is logged and recording stops without affecting syncing.
+    /// See `journal::JournalRecord` for the format of the journal.
+    pub fn with_recorder(mut self, recorder: JournalRecorder) -> Self {
+        self.recorder = Some(JournalWriter::spawn(recorder));
+        self
+    }
+
+    /// Adds a subscriber to the events of the latest subscription, or of the next subscription if none has been started,
+    /// receiving events according to the policy.
+    ///
+    /// Each subscription broadcasts to its own subscribers, which are closed when the subscription exits.
+    /// Subscribers added after the latest subscription exited receive `None`.
+    pub fn subscribe(&self, policy: SubscriberPolicy, capacity: usize) -> StateSpaceSubscriber {
+        self.broadcaster.lock().unwrap().subscribe(policy, capacity)
+    }
+
+    /// Returns the broadcaster for a new subscription, replacing the latest broadcaster if it is claimed by another subscription
+    fn claim_broadcaster(&self) -> Arc<StateSpaceBroadcaster> {
+        let mut broadcaster = self.broadcaster.lock().unwrap();
+        if !broadcaster.claim() {
+            *broadcaster = Arc::new(StateSpaceBroadcaster::new());
+            broadcaster.claim();
+        }
+
+        broadcaster.clone()
+    }
+
+    /// Returns the store holding the AMMs in the state space
+    pub fn state(&self) -> Arc<S> {
+        self.state.clone()
+    }
+
+    /// Returns a replay driver that applies a recorded journal to this state space without a provider.
+    ///
+    /// The replay shares the state and state change cache of the manager, so it should not be used while a subscription is running.
+    pub fn replay(&self, latest_synced_block: u64) -> StateSpaceReplay<S> {
+        StateSpaceReplay::with_cache(
+            self.state.clone(),
+            self.state_change_cache.clone(),
+            latest_synced_block,
+        )
+    }
+
+    /// Returns the number of blocks of state changes retained for unwinding reorgs
+    pub async fn cache_depth(&self) -> usize {
+        self.state_change_cache.read().await.depth()
+    }
+
+    /// Returns the latest snapshot of the state space.
+    ///
+    /// Snapshots are published once each block is fully applied, so they never reflect a partially applied block.
+    /// The snapshot is tagged with block 0 until a subscription is started.
+    pub fn snapshot(&self) -> Arc<StateSpaceSnapshot> {
+        self.snapshots.load()
+    }
+
+    /// Returns a handle to load the latest snapshot of the state space from other tasks
+    pub fn snapshots(&self) -> StateSpaceSnapshots {
+        self.snapshots.clone()
+    }
+
+    /// Sets how logs are fetched for the AMMs in the state space, defaulting to `LogFilterMode::EventSignatures`
+    pub async fn set_filter_mode(&self, filter_mode: LogFilterMode) {
+        self.filters
+            .write()
             .await
-            .values()
-            .flat_map(|amm| amm.sync_on_event_signatures())
-            .collect::<Vec<FixedBytes<32>>>();
+            .set_mode(filter_mode, self.state.as_ref());
+    }
 
-        Filter::new().event_signature(event_signatures)
+    /// Sets the factories to watch for newly created AMMs.
+    ///
+    /// AMMs created by these factories are populated and added to the state space as they are discovered,
+    /// emitting a `StateSpaceEvent::AmmsCreated` event to subscribers.
+    pub async fn set_factories(&self, factories: Vec<Factory>) {
+        self.filters
+            .write()
+            .await
+            .set_factories(factories, self.state.as_ref());
+    }
+
+    /// Returns the filters used to fetch logs for the state space according to the filter mode
+    pub async fn filters(&self) -> Vec<Filter> {
+        self.filters.read().await.filters().to_vec()
     }
 
-    /// Listens to new blocks and handles state changes, sending a Vec<H160> containing each AMM address that incurred a state change in the block.
+    /// Adds AMMs to the state space, refreshing the log filters of any running subscription.
+    ///
+    /// The AMMs should be populated at the latest synced block, as only logs after that block are applied to them.
+    pub async fn add_amms(&self, amms: Vec<AMM>) {
+        let changes = amms
+            .iter()
+            .map(|amm| (amm.address(), Some(amm.clone())))
+            .collect::<Vec<_>>();
+
+        for amm in amms {
+            self.state.insert(amm);
+        }
+
+        self.snapshots.publish_changes(None, &changes);
+        self.refresh_filters().await;
+    }
+
+    /// Removes AMMs from the state space, refreshing the log filters of any running subscription.
+    ///
+    /// Returns the AMMs that were removed. Cached state changes for removed AMMs are retained but not restored on unwind.
+    pub async fn remove_amms(&self, amm_addresses: &[Address]) -> Vec<AMM> {
+        let removed_amms = amm_addresses
+            .iter()
+            .filter_map(|address| self.state.remove(address))
+            .collect::<Vec<AMM>>();
+
+        let changes = removed_amms
+            .iter()
+            .map(|amm| (amm.address(), None))
+            .collect::<Vec<_>>();
+
+        self.snapshots.publish_changes(None, &changes);
+        self.refresh_filters().await;
+
+        removed_amms
+    }
+
+    /// Returns an overlay on the state space for simulating pending transactions
+    pub fn overlay(&self) -> StateSpaceOverlay<S> {
+        StateSpaceOverlay::new(self.state.clone())
+    }
+
+    /// Commits an overlay returned by `overlay` to the state space, publishing a snapshot with the committed AMMs
+    /// and refreshing the log filters of any running subscription.
+    ///
+    /// AMMs updated by a block since they were copied into the overlay are never overwritten, see `StateSpaceOverlay::commit`.
+    /// As with `add_amms`, committed AMMs are not recorded in the state change cache.
+    pub async fn commit_overlay(
+        &self,
+        overlay: StateSpaceOverlay<S>,
+    ) -> Result<Vec<Address>, StateSpaceError> {
+        if !Arc::ptr_eq(overlay.base(), &self.state) {
+            return Err(StateSpaceError::OverlayBaseMismatch);
+        }
+
+        let committed = overlay.commit()?;
+
+        let changes = committed
+            .iter()
+            .map(|address| (*address, self.state.get(address)))
+            .collect::<Vec<_>>();
+        self.snapshots.publish_changes(None, &changes);
+        self.refresh_filters().await;
+
+        Ok(committed)
+    }
+
+    /// Rebuilds the log filters from the AMMs currently in the state space
+    async fn refresh_filters(&self) {
+        self.filters.write().await.refresh(self.state.as_ref());
+    }
+
+    /// Listens to new blocks and handles state changes, broadcasting a `StateSpaceEvent` to the returned subscriber for each state change, reorg and AMM creation.
     pub async fn subscribe_state_changes(
         &self,
         latest_synced_block: u64,
         buffer: usize,
     ) -> Result<
         (
-            Receiver<Vec<Address>>,
+            StateSpaceSubscriber,
+            Vec<JoinHandle<Result<(), StateSpaceError>>>,
+        ),
+        StateSpaceError,
+    > {
+        let (stream_rx, stream_handle) = self.subscribe_blocks_buffered(buffer).await?;
+
+        let (sync_amms_rx, sync_amms_handle) = self
+            .subscribe_sync_amms(latest_synced_block, stream_rx, buffer)
+            .await;
+
+        Ok((sync_amms_rx, vec![stream_handle, sync_amms_handle]))
+    }
+
+    /// Polls the provider for new blocks at `poll_interval` and handles state changes, broadcasting a `StateSpaceEvent` to the returned subscriber for each state change, reorg and AMM creation.
+    ///
+    /// This is a fallback for providers that do not support pubsub subscriptions (e.g. HTTP only nodes).
+    pub async fn subscribe_state_changes_polling(
+        &self,
+        latest_synced_block: u64,
+        buffer: usize,
+        poll_interval: Duration,
+    ) -> Result<
+        (
+            StateSpaceSubscriber,
             Vec<JoinHandle<Result<(), StateSpaceError>>>,
         ),
         StateSpaceError,
     > {
-        let (stream_rx, stream_handle) = self.subscribe_blocks_buffered(buffer).await;
+        let (stream_rx, stream_handle) =
+            self.poll_blocks_buffered(latest_synced_block, buffer, poll_interval);
 
         let (sync_amms_rx, sync_amms_handle) = self
             .subscribe_sync_amms(latest_synced_block, stream_rx, buffer)
@@ -118,23 +359,52 @@ where
         Ok((sync_amms_rx, vec![stream_handle, sync_amms_handle]))
     }
 
+    /// Subscribes to new blocks from the provider and forwards them into a buffered channel.
+    ///
+    /// Returns `StateSpaceError::PubsubUnavailable` if the provider transport does not support subscriptions.
+    /// If the subscription is dropped by the provider, the spawned task will attempt to resubscribe.
     async fn subscribe_blocks_buffered(
         &self,
         buffer: usize,
-    ) -> (Receiver<Block>, JoinHandle<Result<(), StateSpaceError>>) {
-        let (_stream_tx, stream_rx): (Sender<Block>, Receiver<Block>) =
+    ) -> Result<
+        (
+            Receiver<BlockHeader>,
+            JoinHandle<Result<(), StateSpaceError>>,
+        ),
+        StateSpaceError,
+    > {
+        let (stream_tx, stream_rx): (Sender<BlockHeader>, Receiver<BlockHeader>) =
             tokio::sync::mpsc::channel(buffer);
 
-        let _provider = self.provider.clone();
-        let stream_handle = tokio::spawn(async move {
-            // let subscription = provider.subscribe_blocks().await?;
-            // let mut block_stream = subscription.into_stream();
-            // while let Some(block) = block_stream.next().await {
-            //     stream_tx.send(block).await?;
-            // }
+        // Subscribe before spawning the task so that unsupported transports are surfaced to the caller
+        let subscription = subscribe_blocks(self.provider.clone()).await?;
+
+        let provider = self.provider.clone();
+        let stream_handle = tokio::spawn(stream_blocks(provider, subscription, stream_tx));
+
+        Ok((stream_rx, stream_handle))
+    }
+
+    /// Polls `eth_blockNumber` at `poll_interval` and forwards the chain head block into a buffered channel whenever it advances.
+    fn poll_blocks_buffered(
+        &self,
+        latest_polled_block: u64,
+        buffer: usize,
+        poll_interval: Duration,
+    ) -> (
+        Receiver<BlockHeader>,
+        JoinHandle<Result<(), StateSpaceError>>,
+    ) {
+        let (stream_tx, stream_rx): (Sender<BlockHeader>, Receiver<BlockHeader>) =
+            tokio::sync::mpsc::channel(buffer);
 
-            Ok::<(), StateSpaceError>(())
-        });
+        let provider = self.provider.clone();
+        let stream_handle = tokio::spawn(poll_blocks(
+            provider,
+            latest_polled_block,
+            poll_interval,
+            stream_tx,
+        ));
 
         (stream_rx, stream_handle)
     }
@@ -142,60 +412,259 @@ where
     pub async fn subscribe_sync_amms(
         &self,
         mut latest_synced_block: u64,
-        mut stream_rx: Receiver<Block>,
+        mut stream_rx: Receiver<BlockHeader>,
         buffer: usize,
     ) -> (
-        Receiver<Vec<Address>>,
+        StateSpaceSubscriber,
         JoinHandle<Result<(), StateSpaceError>>,
     ) {
         let state = self.state.clone();
         let provider = self.provider.clone();
-        let filter = self.filter().await;
+        let state_space_filters = self.filters.clone();
         let state_change_cache = self.state_change_cache.clone();
+        let snapshots = self.snapshots.clone();
+        let recorder = self.recorder.clone();
+
+        // Publish a snapshot of the state space at the block that the subscription starts from
+        snapshots.publish(StateSpaceSnapshot::new(
+            latest_synced_block,
+            self.state.amms(),
+        ));
 
-        let (amms_updated_tx, amms_updated_rx) = tokio::sync::mpsc::channel(buffer);
+        // Reorgs deeper than the state change cache are resynced against the state the subscription starts from
+        let mut resync_base = snapshots.load();
+
+        let amms_updated_tx = self.claim_broadcaster();
+        let amms_updated_rx = amms_updated_tx.subscribe(SubscriberPolicy::Block, buffer);
 
         let updated_amms_handle: JoinHandle<Result<(), StateSpaceError>> =
             tokio::spawn(async move {
+                // Close the subscribers once the task exits so they are not left waiting for events
+                let _close_subscribers = amms_updated_tx.close_on_drop();
+
                 while let Some(block) = stream_rx.recv().await {
-                    let chain_head_block_number = block.header.number;
+                    let chain_head_block_number = block.number;
+
+                    // Skip blocks that have already been synced, recording them so the journal reflects exactly what was received.
+                    // Blocks up to the latest synced block without a cached hash were synced before the block history began.
+                    let cached_block_hash = state_change_cache
+                        .read()
+                        .await
+                        .block_hash(chain_head_block_number);
+                    if cached_block_hash == Some(block.hash)
+                        || (cached_block_hash.is_none()
+                            && chain_head_block_number <= latest_synced_block)
+                    {
+                        record(&recorder, vec![JournalRecord::Header(block)]);
+                        continue;
+                    }
+
+                    // Records for the block, written together once its logs have been fetched
+                    let journal = std::sync::Mutex::new(vec![JournalRecord::Header(block)]);
+
+                    // Events are sent once the block is fully applied and its snapshot has been published
+                    let mut events = vec![];
+
+                    // Walk back from the new block via parent hashes to find where it joins the synced chain
+                    let canonical_blocks = match find_common_ancestor(
+                        state_change_cache.clone(),
+                        &block,
+                        |block_hash| {
+                            get_recorded_block_header(provider.clone(), &journal, block_hash)
+                        },
+                    )
+                    .await
+                    {
+                        // If the common ancestor is behind the latest synced block, a reorg has occurred
+                        Ok((common_ancestor, canonical_blocks))
+                            if common_ancestor < latest_synced_block =>
+                        {
+                            tracing::trace!(
+                                chain_head_block_number,
+                                latest_synced_block,
+                                common_ancestor,
+                                "reorg detected, unwinding state changes"
+                            );
+
+                            match unwind_state_changes(
+                                state.clone(),
+                                state_change_cache.clone(),
+                                common_ancestor + 1,
+                            )
+                            .await
+                            {
+                                Ok(unwound_events) => {
+                                    // Created AMMs may have been removed, so the filters are rebuilt before fetching logs
+                                    if unwound_events.iter().any(|event| {
+                                        matches!(event, StateSpaceEvent::AmmsRemoved(_))
+                                    }) {
+                                        state_space_filters.write().await.refresh(state.as_ref());
+                                    }
+
+                                    events.extend(unwound_events);
+                                    latest_synced_block = common_ancestor;
+                                    Some(canonical_blocks)
+                                }
+                                Err(StateSpaceError::ReorgTooDeep(..)) => None,
+                                Err(err) => return Err(err),
+                            }
+                        }
+                        Ok((_, canonical_blocks)) => Some(canonical_blocks),
+                        Err(StateSpaceError::ReorgTooDeep(..)) => None,
+                        Err(err) => return Err(err),
+                    };
 
-                    // If the chain head block number <= latest synced block, a reorg has occurred
-                    if chain_head_block_number <= latest_synced_block {
-                        tracing::trace!(
+                    // If the reorg is deeper than the cache, the state can not be unwound so resync it at the new head
+                    let Some(canonical_blocks) = canonical_blocks else {
+                        tracing::warn!(
                             chain_head_block_number,
                             latest_synced_block,
-                            "reorg detected, unwinding state changes"
+                            "reorg deeper than state change cache, resyncing state space"
                         );
 
-                        latest_synced_block = unwind_state_changes(
+                        // Uniswap V3 pools added since the resync base rebuild their ticks from the earliest tracked Uniswap V3 factory
+                        let (filters, tick_data_from_block) = {
+                            let state_space_filters = state_space_filters.read().await;
+                            let tick_data_from_block = state_space_filters
+                                .factories()
+                                .iter()
+                                .filter(|factory| matches!(factory, Factory::UniswapV3Factory(_)))
+                                .map(|factory| factory.creation_block())
+                                .min();
+
+                            (state_space_filters.filters().to_vec(), tick_data_from_block)
+                        };
+
+                        let changes = resync_state_space(
                             state.clone(),
                             state_change_cache.clone(),
-                            chain_head_block_number,
+                            &resync_base,
+                            &block,
+                            &filters,
+                            tick_data_from_block,
+                            provider.clone(),
                         )
-                        .await;
-                    }
+                        .await?;
+
+                        record(&recorder, journal.into_inner().unwrap());
+
+                        snapshots.publish(StateSpaceSnapshot::new(
+                            chain_head_block_number,
+                            state.amms(),
+                        ));
+                        resync_base = snapshots.load();
+
+                        amms_updated_tx
+                            .send(StateSpaceEvent::Resynced {
+                                block_number: chain_head_block_number,
+                                block_hash: block.hash,
+                                changes,
+                            })
+                            .await;
+
+                        latest_synced_block = chain_head_block_number;
+                        continue;
+                    };
 
                     // Get logs from the provider that match the event signatures from the state space
-                    let logs = provider
-                        .get_logs(
-                            &filter
-                                .clone()
-                                .from_block(latest_synced_block + 1)
-                                .to_block(chain_head_block_number),
-                        )
-                        .await?;
+                    let filters = state_space_filters.read().await.filters().to_vec();
+                    let logs = get_logs_for_filters(
+                        &filters,
+                        latest_synced_block + 1,
+                        chain_head_block_number,
+                        provider.clone(),
+                    )
+                    .await?;
 
-                    // Handle any state changes from the logs
-                    if !logs.is_empty() {
-                        let amms_updated = handle_state_changes_from_logs(
+                    // Separate AMM created logs from tracked factories
+                    let (amm_created_logs, mut logs): (Vec<Log>, Vec<Log>) = {
+                        let state_space_filters = state_space_filters.read().await;
+                        logs.into_iter()
+                            .partition(|log| state_space_filters.factory_for_log(log).is_some())
+                    };
+
+                    // Add any newly created AMMs before handling state changes, so that logs after their creation block are applied to them
+                    let mut amms_created = vec![];
+                    if !amm_created_logs.is_empty() {
+                        amms_created = add_amms_from_logs(
                             state.clone(),
                             state_change_cache.clone(),
-                            logs,
+                            state_space_filters.clone(),
+                            amm_created_logs,
+                            provider.clone(),
                         )
-                        .await?;
+                        .await;
+
+                        if !amms_created.is_empty() {
+                            let filter_mode = state_space_filters.read().await.mode();
+                            logs = merge_created_amm_logs(
+                                logs,
+                                &amms_created,
+                                filter_mode,
+                                latest_synced_block + 1,
+                                chain_head_block_number,
+                                provider.clone(),
+                            )
+                            .await?;
+                        }
+                    }
+
+                    // Record the created AMMs and the logs as applied, so that a replay does not need a provider to populate them
+                    if recorder.is_some() {
+                        let mut records = journal.into_inner().unwrap();
+                        records.extend(amms_created.iter().map(|(amm, block_number)| {
+                            JournalRecord::AmmCreated {
+                                block_number: *block_number,
+                                amm: Box::new(amm.clone()),
+                            }
+                        }));
+                        records.push(JournalRecord::Logs {
+                            from_block: latest_synced_block + 1,
+                            to_block: chain_head_block_number,
+                            logs: logs.clone(),
+                        });
+                        record(&recorder, records);
+                    }
 
-                        amms_updated_tx.send(amms_updated).await?;
+                    if !amms_created.is_empty() {
+                        events.push(StateSpaceEvent::AmmsCreated(
+                            amms_created.into_iter().map(|(amm, _)| amm).collect(),
+                        ));
+                    }
+
+                    // Handle any state changes from the logs
+                    let state_change_events = handle_state_changes_from_logs(
+                        state.clone(),
+                        state_change_cache.clone(),
+                        logs,
+                    )
+                    .await?;
+
+                    events.extend(
+                        state_change_events
+                            .into_iter()
+                            .map(StateSpaceEvent::StateChanged),
+                    );
+
+                    // Record the hashes of the newly synced blocks so that the next block can be verified against them
+                    let mut cache = state_change_cache.write().await;
+                    for (block_number, block_hash) in canonical_blocks {
+                        cache.add_block_hash(block_number, block_hash);
+                    }
+                    drop(cache);
+
+                    // Publish a snapshot with the AMMs affected by the block
+                    let changes = events
+                        .iter()
+                        .flat_map(StateSpaceEvent::amm_addresses)
+                        .collect::<HashSet<Address>>()
+                        .into_iter()
+                        .map(|address| (address, state.get(&address)))
+                        .collect::<Vec<_>>();
+                    snapshots.publish_changes(Some(chain_head_block_number), &changes);
+
+                    for event in events {
+                        amms_updated_tx.send(event).await;
                     }
 
                     // Once all amms are synced, update the latest synced block
@@ -209,69 +678,459 @@ where
     }
 }
 
+/// Subscribes to new blocks, mapping an unsupported transport to `StateSpaceError::PubsubUnavailable`
+async fn subscribe_blocks<N, P>(
+    provider: Arc<P>,
+) -> Result<Subscription<N::HeaderResponse>, StateSpaceError>
+where
+    N: Network,
+    P: Provider<N>,
+{
+    provider.subscribe_blocks().await.map_err(|err| match err {
+        RpcError::Transport(TransportErrorKind::PubsubUnavailable) => {
+            StateSpaceError::PubsubUnavailable
+        }
+        err => StateSpaceError::TransportError(err),
+    })
+}
+
+/// Forwards blocks from the subscription into `stream_tx`, resubscribing whenever the subscription is dropped
+async fn stream_blocks<N, P>(
+    provider: Arc<P>,
+    mut subscription: Subscription<N::HeaderResponse>,
+    stream_tx: Sender<BlockHeader>,
+) -> Result<(), StateSpaceError>
+where
+    N: Network,
+    P: Provider<N>,
+{
+    loop {
+        let mut block_stream = subscription.into_stream();
+        while let Some(header) = block_stream.next().await {
+            stream_tx.send(BlockHeader::from_header(&header)).await?;
+        }
+
+        tracing::warn!("block subscription dropped, resubscribing");
+        subscription = resubscribe_blocks(provider.clone()).await?;
+    }
+}
+
+/// Attempts to resubscribe to new blocks with a linear backoff between attempts
+async fn resubscribe_blocks<N, P>(
+    provider: Arc<P>,
+) -> Result<Subscription<N::HeaderResponse>, StateSpaceError>
+where
+    N: Network,
+    P: Provider<N>,
+{
+    let mut attempt = 1;
+    loop {
+        match subscribe_blocks(provider.clone()).await {
+            Ok(subscription) => return Ok(subscription),
+            Err(StateSpaceError::PubsubUnavailable) => {
+                return Err(StateSpaceError::PubsubUnavailable)
+            }
+            Err(err) if attempt >= MAX_RESUBSCRIBE_ATTEMPTS => return Err(err),
+            Err(err) => {
+                tracing::warn!(?err, attempt, "failed to resubscribe to blocks, retrying");
+                tokio::time::sleep(RESUBSCRIBE_BACKOFF * attempt).await;
+                attempt += 1;
+            }
+        }
+    }
+}
+
+/// Polls the chain head at `poll_interval`, forwarding the head block into `stream_tx` whenever it advances past `latest_polled_block`.
+///
+/// Transient provider errors are retried with a linear backoff, giving up after `MAX_RESUBSCRIBE_ATTEMPTS` consecutive failures.
+async fn poll_blocks<N, P>(
+    provider: Arc<P>,
+    mut latest_polled_block: u64,
+    poll_interval: Duration,
+    stream_tx: Sender<BlockHeader>,
+) -> Result<(), StateSpaceError>
+where
+    N: Network,
+    P: Provider<N>,
+{
+    let mut interval = tokio::time::interval(poll_interval);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    let mut attempt = 1;
+    loop {
+        interval.tick().await;
+
+        let block = match poll_chain_head(provider.clone(), latest_polled_block).await {
+            Ok(block) => {
+                attempt = 1;
+                block
+            }
+            Err(err) if attempt >= MAX_RESUBSCRIBE_ATTEMPTS => return Err(err),
+            Err(err) => {
+                tracing::warn!(?err, attempt, "failed to poll the chain head, retrying");
+                tokio::time::sleep(RESUBSCRIBE_BACKOFF * attempt).await;
+                attempt += 1;
+                continue;
+            }
+        };
+
+        if let Some(block) = block {
+            latest_polled_block = block.number;
+            stream_tx.send(block).await?;
+        }
+    }
+}
+
+/// Returns the chain head block if it is past `latest_polled_block`
+async fn poll_chain_head<N, P>(
+    provider: Arc<P>,
+    latest_polled_block: u64,
+) -> Result<Option<BlockHeader>, StateSpaceError>
+where
+    N: Network,
+    P: Provider<N>,
+{
+    let chain_head_block_number = provider.get_block_number().await?;
+    if chain_head_block_number <= latest_polled_block {
+        return Ok(None);
+    }
+
+    let block = provider
+        .get_block_by_number(BlockNumberOrTag::Number(chain_head_block_number))
+        .await?
+        .ok_or(StateSpaceError::BlockNumberNotFound)?;
+
+    Ok(Some(BlockHeader::from_header(block.header())))
+}
+
+/// Creates new AMMs from the AMM created logs of tracked factories and adds them to the state space.
+///
+/// Each AMM is populated as of the end of its creation block, and its creation is recorded in the state change cache
+/// so that it is removed if the block is unwound. AMMs that fail to populate or are already tracked are skipped.
+/// Returns the AMMs that were added along with the block each was populated at.
+async fn add_amms_from_logs<N, P, S>(
+    state: Arc<S>,
+    state_change_cache: Arc<RwLock<StateChangeCache>>,
+    state_space_filters: Arc<RwLock<StateSpaceFilters>>,
+    logs: Vec<Log>,
+    provider: Arc<P>,
+) -> Vec<(AMM, u64)>
+where
+    N: Network,
+    P: Provider<N>,
+    S: StateSpaceStore,
+{
+    let mut amms = vec![];
+    for log in logs {
+        let Some(factory) = state_space_filters
+            .read()
+            .await
+            .factory_for_log(&log)
+            .cloned()
+        else {
+            continue;
+        };
+
+        let Some(block_number) = log.block_number else {
+            tracing::warn!(?factory, "amm created log is missing a block number");
+            continue;
+        };
+
+        match factory.new_amm_from_log(log, provider.clone()).await {
+            Ok(amm) if state.contains(&amm.address()) => {}
+            Ok(amm) => amms.push((amm, block_number)),
+            Err(err) => tracing::warn!(?err, ?factory, "failed to create amm from log"),
+        }
+    }
+
+    let mut cache = state_change_cache.write().await;
+    for (amm, block_number) in amms.iter() {
+        state.insert(amm.clone());
+        cache.add_created_amm(*block_number, amm.address());
+    }
+    drop(cache);
+
+    // Refresh the filters so that logs for the new AMMs are fetched
+    state_space_filters.write().await.refresh(state.as_ref());
+
+    amms
+}
+
+/// Merges the logs of newly created AMMs into the logs of a block range, dropping any logs already reflected in the state
+/// each AMM was populated at.
+///
+/// Logs for the created AMMs are only fetched separately when filtering by address, since otherwise they are already included.
+async fn merge_created_amm_logs<N, P>(
+    mut logs: Vec<Log>,
+    amms_created: &[(AMM, u64)],
+    filter_mode: LogFilterMode,
+    from_block: u64,
+    to_block: u64,
+    provider: Arc<P>,
+) -> Result<Vec<Log>, StateSpaceError>
+where
+    N: Network,
+    P: Provider<N>,
+{
+    if let LogFilterMode::Addresses { .. } = filter_mode {
+        let mut event_signatures = HashSet::new();
+        let mut addresses = vec![];
+        for (amm, _) in amms_created {
+            event_signatures.extend(amm.sync_on_event_signatures());
+            addresses.push(amm.address());
+        }
+
+        let filters = filter_mode.filters(event_signatures.into_iter().collect(), &addresses);
+        logs.extend(get_logs_for_filters(&filters, from_block, to_block, provider).await?);
+        logs.sort_by_key(|log| (log.block_number, log.log_index));
+    }
+
+    let populated_blocks = amms_created
+        .iter()
+        .map(|(amm, block_number)| (amm.address(), *block_number))
+        .collect::<HashMap<Address, u64>>();
+
+    logs.retain(|log| {
+        populated_blocks
+            .get(&log.address())
+            .is_none_or(|populated_block| {
+                log.block_number
+                    .is_some_and(|block_number| block_number > *populated_block)
+            })
+    });
+
+    Ok(logs)
+}
+
+/// Fetches the header of the block with the given hash from the provider
+async fn get_block_header<N, P>(
+    provider: Arc<P>,
+    block_hash: B256,
+) -> Result<Option<BlockHeader>, StateSpaceError>
+where
+    N: Network,
+    P: Provider<N>,
+{
+    let block = provider.get_block_by_hash(block_hash).await?;
+    Ok(block.map(|block| BlockHeader::from_header(block.header())))
+}
+
+/// Fetches the header of the block with the given hash, adding it to the journal records of the block as an ancestor
+async fn get_recorded_block_header<N, P>(
+    provider: Arc<P>,
+    journal: &std::sync::Mutex<Vec<JournalRecord>>,
+    block_hash: B256,
+) -> Result<Option<BlockHeader>, StateSpaceError>
+where
+    N: Network,
+    P: Provider<N>,
+{
+    let header = get_block_header(provider, block_hash).await?;
+    if let Some(header) = header {
+        journal
+            .lock()
+            .unwrap()
+            .push(JournalRecord::Ancestor(header));
+    }
+
+    Ok(header)
+}
+
+/// Writes the records to the journal if a recorder is set
+fn record(recorder: &Option<JournalWriter>, records: Vec<JournalRecord>) {
+    if let Some(recorder) = recorder {
+        recorder.record(records);
+    }
+}
+
+/// Walks back from `header` via parent hashes until reaching a block recorded in the state change cache.
+/// Parent headers are fetched with `get_block_header`.
+///
+/// Returns the number of the common ancestor along with the number and hash of each block
+/// from the ancestor (exclusive) to `header` (inclusive), in ascending order.
+/// If the block history is empty, the parent of `header` is treated as the common ancestor.
+/// Returns `StateSpaceError::ReorgTooDeep` if the fork point is older than the block history.
+async fn find_common_ancestor<F, Fut>(
+    state_change_cache: Arc<RwLock<StateChangeCache>>,
+    header: &BlockHeader,
+    mut get_block_header: F,
+) -> Result<(u64, Vec<(u64, B256)>), StateSpaceError>
+where
+    F: FnMut(B256) -> Fut,
+    Fut: Future<Output = Result<Option<BlockHeader>, StateSpaceError>>,
+{
+    let mut canonical_blocks = vec![(header.number, header.hash)];
+    let mut block_number = header.number.saturating_sub(1);
+    let mut parent_hash = header.parent_hash;
+    let mut reorged = false;
+
+    loop {
+        let (recorded_hash, bounds) = {
+            let cache = state_change_cache.read().await;
+            (cache.block_hash(block_number), cache.block_hash_bounds())
+        };
+
+        match (recorded_hash, bounds) {
+            // The parent is the block we synced at this height, so this is the common ancestor
+            (Some(recorded_hash), _) if recorded_hash == parent_hash => break,
+            // The block we synced at this height was reorged out
+            (Some(_), _) => reorged = true,
+            // There is no history to verify against
+            (None, None) => break,
+            // The fork point is older than the history, so the common ancestor can not be verified
+            (None, Some((oldest, _))) if block_number < oldest => {
+                if reorged {
+                    return Err(StateSpaceError::ReorgTooDeep(block_number + 1, oldest));
+                }
+                break;
+            }
+            // The parent is ahead of the history
+            _ => {}
+        }
+
+        if block_number == 0 {
+            break;
+        }
+
+        let parent = get_block_header(parent_hash)
+            .await?
+            .ok_or(StateSpaceError::BlockNumberNotFound)?;
+
+        canonical_blocks.push((block_number, parent_hash));
+        parent_hash = parent.parent_hash;
+        block_number -= 1;
+    }
+
+    canonical_blocks.reverse();
+
+    Ok((block_number, canonical_blocks))
+}
+
 #[derive(Debug, Clone)]
 pub struct StateChange {
     pub state_change: Vec<AMM>,
     pub block_number: u64,
+    pub block_hash: B256,
 }
 
 impl StateChange {
-    pub fn new(state_change: Vec<AMM>, block_number: u64) -> Self {
+    pub fn new(state_change: Vec<AMM>, block_number: u64, block_hash: B256) -> Self {
         Self {
             block_number,
+            block_hash,
             state_change,
         }
     }
 }
 
-pub async fn handle_state_changes_from_logs(
-    state: Arc<RwLock<StateSpace>>,
+/// Syncs the AMMs in the state space from the logs, committing the previous state of each block to the state change cache.
+///
+/// Returns a state change event for each block containing logs from tracked AMMs.
+pub async fn handle_state_changes_from_logs<S: StateSpaceStore>(
+    state: Arc<S>,
     state_change_cache: Arc<RwLock<StateChangeCache>>,
     logs: Vec<Log>,
-) -> Result<Vec<Address>, StateSpaceError> {
-    // If there are no logs to process, return early
-    let Some(log) = logs.first() else {
-        return Ok(vec![]);
-    };
-
-    // Track the block number for the most recently processed
-    // log to determine when to commit state changes to cache
-    let mut last_log_block_number = get_block_number_from_log(log)?;
+) -> Result<Vec<StateChangeEvent>, StateSpaceError> {
+    let mut state_change_events = vec![];
 
+    // Event for the block of the most recently processed log, used to determine when to commit state changes to cache
+    let mut state_change_event: Option<StateChangeEvent> = None;
     let mut prev_state = vec![];
-    let mut updated_amms = HashSet::new();
 
     // For each log, check if the log is from an amm in the state space and sync the updates
     for log in logs.into_iter() {
         let log_block_number = get_block_number_from_log(&log)?;
+        let log_block_hash = get_block_hash_from_log(&log)?;
 
-        let log_address = log.address();
-        if let Some(amm) = state.write().await.get_mut(&log_address) {
-            updated_amms.insert(log_address);
+        // If the block has changed, commit the state changes of the previous block to the cache
+        if state_change_event
+            .as_ref()
+            .is_none_or(|event| event.block_hash != log_block_hash)
+        {
+            if let Some(event) = state_change_event.take() {
+                commit_state_changes(
+                    &mut prev_state,
+                    event.block_number,
+                    event.block_hash,
+                    state_change_cache.clone(),
+                )
+                .await;
 
-            // Push the state of the amm before syncing to cache and then update the state
-            prev_state.push(amm.clone());
-            amm.sync_from_log(log)?;
+                if !event.is_empty() {
+                    state_change_events.push(event);
+                }
+            }
         }
 
-        // If the block number has changed, commit the state changes to the cache
-        if log_block_number != last_log_block_number {
+        let event = state_change_event
+            .get_or_insert_with(|| StateChangeEvent::new(log_block_number, log_block_hash));
+
+        let log_address = log.address();
+
+        // Removed logs belong to a block that was reorged out, so unwind the affected amm instead of syncing it
+        if log.removed {
+            // Commit the pending state changes first, so that logs applied earlier in this batch can be unwound
             commit_state_changes(
                 &mut prev_state,
-                last_log_block_number,
+                event.block_number,
+                event.block_hash,
                 state_change_cache.clone(),
             )
             .await;
 
-            last_log_block_number = log_block_number;
+            let prev_amm = state_change_cache
+                .write()
+                .await
+                .unwind_amm_state_changes(log_address, log_block_number)?;
+
+            // Only restore the amm if it is still tracked, since it may have been removed from the state space
+            if let Some(amm) = prev_amm {
+                if let Some(unwound_amm) = state.replace(amm.clone()) {
+                    tracing::trace!(
+                        ?log_address,
+                        log_block_number,
+                        "unwound amm from removed log"
+                    );
+
+                    event.record_change(log, unwound_amm, amm);
+                }
+            }
+
+            continue;
+        }
+
+        // Sync the amm in place, keeping the state of the amm before syncing
+        let synced_amm = state
+            .update(&log_address, |amm| {
+                let prev_amm = amm.clone();
+                amm.sync_from_log(log.clone())
+                    .map(|_| (prev_amm, amm.clone()))
+            })
+            .transpose()?;
+
+        // Push the state of the amm before syncing to cache
+        if let Some((prev_amm, amm)) = synced_amm {
+            prev_state.push(prev_amm.clone());
+            event.record_change(log, prev_amm, amm);
         }
     }
 
     // Commit the state changes for the last block
-    commit_state_changes(&mut prev_state, last_log_block_number, state_change_cache).await;
+    if let Some(event) = state_change_event {
+        commit_state_changes(
+            &mut prev_state,
+            event.block_number,
+            event.block_hash,
+            state_change_cache,
+        )
+        .await;
+
+        if !event.is_empty() {
+            state_change_events.push(event);
+        }
+    }
 
-    // Return the addresses of the amms that were affected
-    Ok(updated_amms.into_iter().collect())
+    Ok(state_change_events)
 }
 
 /// Commits state changes contained in `prev_state` to the state change cache
@@ -279,36 +1138,194 @@ pub async fn handle_state_changes_from_logs(
 async fn commit_state_changes(
     prev_state: &mut Vec<AMM>,
     block_number: u64,
+    block_hash: B256,
     state_change_cache: Arc<RwLock<StateChangeCache>>,
 ) {
     if !prev_state.is_empty() {
-        let state_change = StateChange::new(prev_state.clone(), block_number);
+        let state_change = StateChange::new(prev_state.clone(), block_number, block_hash);
 
-        let _ = state_change_cache
+        if let Err(err) = state_change_cache
             .write()
             .await
-            .add_state_change_to_cache(state_change);
+            .add_state_change_to_cache(state_change)
+        {
+            tracing::warn!(?err, "failed to add state change to the cache");
+        }
     };
     prev_state.clear();
 }
 
-/// Unwinds the state changes up to the specified block number
-async fn unwind_state_changes(
-    state: Arc<RwLock<StateSpace>>,
+/// Unwinds the state changes for all blocks at or above `block_to_unwind`, removing any AMMs created in those blocks.
+///
+/// Returns a `StateSpaceEvent::Unwound` event with the state of each restored AMM before and after unwinding,
+/// followed by a `StateSpaceEvent::AmmsRemoved` event if any created AMMs were removed.
+async fn unwind_state_changes<S: StateSpaceStore>(
+    state: Arc<S>,
     state_change_cache: Arc<RwLock<StateChangeCache>>,
-    chain_head_block_number: u64,
-) -> u64 {
-    let updated_amms = state_change_cache
-        .write()
-        .await
-        .unwind_state_changes(chain_head_block_number);
-
-    let mut state_writer = state.write().await;
+    block_to_unwind: u64,
+) -> Result<Vec<StateSpaceEvent>, StateSpaceError> {
+    let mut cache = state_change_cache.write().await;
+    let updated_amms = cache.unwind_state_changes(block_to_unwind)?;
+    let created_amms = cache.unwind_created_amms(block_to_unwind);
+    drop(cache);
+
+    // Only restore AMMs that are still tracked, since AMMs may have been removed from the state space
+    let mut changes = vec![];
     for amm in updated_amms {
-        state_writer.insert(amm.address(), amm);
+        if let Some(unwound_amm) = state.replace(amm.clone()) {
+            changes.push(AmmStateChange::new(unwound_amm, amm));
+        }
+    }
+
+    let mut events = vec![StateSpaceEvent::Unwound {
+        block_number: block_to_unwind,
+        changes,
+    }];
+
+    let removed_amms = created_amms
+        .iter()
+        .filter_map(|address| state.remove(address))
+        .collect::<Vec<AMM>>();
+    if !removed_amms.is_empty() {
+        events.push(StateSpaceEvent::AmmsRemoved(removed_amms));
+    }
+
+    Ok(events)
+}
+
+/// Clears the state change cache and repopulates the AMMs affected by a reorg deeper than the cache at `block`.
+///
+/// Since the fork point can not be determined, `base` is a snapshot of the state space at a block on the canonical chain,
+/// such as the block the subscription started from. The AMMs that changed since the base or have logs between the base block
+/// and `block` are repopulated, and the ticks of Uniswap V3 pools are rebuilt from their ticks in the base and the mint and burn logs since.
+/// Uniswap V3 pools added after the base rebuild their ticks from `tick_data_from_block` instead, keeping their ticks if it is `None`.
+/// Returns the state of each resynced AMM before and after resyncing.
+async fn resync_state_space<N, P, S>(
+    state: Arc<S>,
+    state_change_cache: Arc<RwLock<StateChangeCache>>,
+    base: &StateSpaceSnapshot,
+    block: &BlockHeader,
+    filters: &[Filter],
+    tick_data_from_block: Option<u64>,
+    provider: Arc<P>,
+) -> Result<Vec<AmmStateChange>, StateSpaceError>
+where
+    N: Network,
+    P: Provider<N>,
+    S: StateSpaceStore,
+{
+    let logs = if base.block_number() < block.number {
+        get_logs_for_filters(
+            filters,
+            base.block_number() + 1,
+            block.number,
+            provider.clone(),
+        )
+        .await?
+    } else {
+        vec![]
+    };
+
+    let mut logs_by_address: HashMap<Address, Vec<Log>> = HashMap::new();
+    for log in logs {
+        logs_by_address.entry(log.address()).or_default().push(log);
+    }
+
+    let affected_amms = state
+        .amms()
+        .into_iter()
+        .filter(|amm| {
+            logs_by_address.contains_key(&amm.address())
+                || !same_state(base.get(&amm.address()), Some(amm))
+        })
+        .collect::<Vec<AMM>>();
+
+    // Rebuild the ticks before populating, since the populated liquidity and tick must not be changed by the mint and burn logs
+    let mut pools_without_base = vec![];
+    let mut resynced_amms = vec![];
+    for mut amm in affected_amms {
+        if let AMM::UniswapV3Pool(pool) = &mut amm {
+            match base.get(&pool.address) {
+                Some(AMM::UniswapV3Pool(base_pool)) => {
+                    rebuild_ticks(
+                        pool,
+                        base_pool,
+                        logs_by_address.remove(&pool.address).unwrap_or_default(),
+                    )?;
+                }
+                _ => {
+                    pools_without_base.push(amm);
+                    continue;
+                }
+            }
+        }
+
+        resynced_amms.push(amm);
+    }
+
+    if !pools_without_base.is_empty() {
+        match tick_data_from_block {
+            Some(from_block) => {
+                populate_tick_data(
+                    &mut pools_without_base,
+                    from_block,
+                    block.number,
+                    provider.clone(),
+                )
+                .await?
+            }
+            None => tracing::warn!(
+                pools = pools_without_base.len(),
+                "no uniswap v3 factory to rebuild the ticks of pools added since the resync base, keeping their ticks"
+            ),
+        }
+        resynced_amms.extend(pools_without_base);
+    }
+
+    // Populate each type of AMM separately since batch requests require congruent AMMs
+    let (uniswap_v2_pools, uniswap_v3_pools, erc_4626_vaults) = sort_amms(resynced_amms);
+
+    let mut populated_amms = vec![];
+    for mut amms in [uniswap_v2_pools, uniswap_v3_pools, erc_4626_vaults] {
+        if amms.is_empty() {
+            continue;
+        }
+
+        populate_amms(&mut amms, block.number, provider.clone()).await?;
+        populated_amms.extend(amms);
+    }
+
+    let mut cache = state_change_cache.write().await;
+    cache.clear();
+    cache.add_block_hash(block.number, block.hash);
+    drop(cache);
+
+    let mut changes = vec![];
+    for amm in populated_amms {
+        // AMMs removed from the state space while resyncing are not added back
+        if let Some(prev_amm) = state.replace(amm.clone()) {
+            changes.push(AmmStateChange::new(prev_amm, amm));
+        }
+    }
+
+    Ok(changes)
+}
+
+/// Rebuilds the ticks of `pool` from the ticks of `base_pool` and the mint and burn logs of the pool since the base,
+/// leaving its liquidity, price and tick untouched
+fn rebuild_ticks(
+    pool: &mut UniswapV3Pool,
+    base_pool: &UniswapV3Pool,
+    logs: Vec<Log>,
+) -> Result<(), EventLogError> {
+    pool.tick_bitmap = base_pool.tick_bitmap.clone();
+    pool.ticks = base_pool.ticks.clone();
+
+    for log in logs {
+        pool.sync_ticks_from_log(log)?;
     }
 
-    chain_head_block_number - 1
+    Ok(())
 }
 
 /// Extracts the block number from a log
@@ -319,3 +1336,433 @@ pub fn get_block_number_from_log(log: &Log) -> Result<u64, EventLogError> {
         Err(EventLogError::LogBlockNumberNotFound)
     }
 }
+
+/// Extracts the block hash from a log
+pub fn get_block_hash_from_log(log: &Log) -> Result<B256, EventLogError> {
+    if let Some(block_hash) = log.block_hash {
+        Ok(block_hash)
+    } else {
+        Err(EventLogError::LogBlockHashNotFound)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use alloy::{
+        dyn_abi::DynSolValue,
+        network::Ethereum,
+        primitives::{address, Address, Bytes, B256, U256},
+        providers::{ProviderBuilder, RootProvider},
+        rpc::types::eth::Log,
+        sol_types::SolEvent,
+        transports::mock::Asserter,
+    };
+    use tokio::sync::RwLock;
+
+    use super::{
+        cache::StateChangeCache,
+        event::StateSpaceEvent,
+        filter::LogFilterMode,
+        handle_state_changes_from_logs,
+        journal::{BlockHeader, JournalRecord},
+        rebuild_ticks,
+        recorder::{read_journal_directory, JournalRecorder},
+        replay::StateSpaceReplay,
+        store::{LockedStateSpace, StateSpaceStore},
+        subscriber::SubscriberPolicy,
+        unwind_state_changes, StateChange, StateSpaceManager,
+    };
+    use crate::amm::{
+        factory::Factory,
+        uniswap_v2::{
+            factory::{IUniswapV2Factory, UniswapV2Factory},
+            IUniswapV2Pair, UniswapV2Pool,
+        },
+        uniswap_v3::{IUniswapV3Pool, UniswapV3Pool},
+        AutomatedMarketMaker, AMM,
+    };
+
+    const POOL_ADDRESS: Address = address!("B4e16d0168e52d35CaCD2c6185b44281Ec28C9Dc");
+    const CREATED_POOL_ADDRESS: Address = address!("A478c2975Ab1Ea89e8196811F51A7B7Ade33eB11");
+
+    fn pool_with_reserve(address: Address, reserve_0: u128) -> AMM {
+        AMM::UniswapV2Pool(UniswapV2Pool {
+            address,
+            reserve_0,
+            ..Default::default()
+        })
+    }
+
+    fn sync_log(block_number: u64, reserve: u128, removed: bool) -> Log {
+        let sync = IUniswapV2Pair::Sync {
+            reserve0: reserve.try_into().unwrap(),
+            reserve1: reserve.try_into().unwrap(),
+        };
+
+        Log {
+            inner: alloy::primitives::Log {
+                address: POOL_ADDRESS,
+                data: sync.encode_log_data(),
+            },
+            block_number: Some(block_number),
+            block_hash: Some(B256::with_last_byte(block_number as u8)),
+            removed,
+            ..Default::default()
+        }
+    }
+
+    fn position_log(event: impl SolEvent, block_number: u64) -> Log {
+        Log {
+            inner: alloy::primitives::Log {
+                address: CREATED_POOL_ADDRESS,
+                data: event.encode_log_data(),
+            },
+            block_number: Some(block_number),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_rebuild_ticks_keeps_liquidity() {
+        let mut base_pool = UniswapV3Pool {
+            address: CREATED_POOL_ADDRESS,
+            tick_spacing: 10,
+            ..Default::default()
+        };
+        base_pool.update_position(-10, 10, 1000);
+
+        // The pool is populated at the resync block, with the liquidity of the position that is still in range
+        let mut pool = UniswapV3Pool {
+            address: CREATED_POOL_ADDRESS,
+            liquidity: 600,
+            tick_spacing: 10,
+            ..Default::default()
+        };
+
+        let logs = vec![
+            position_log(
+                IUniswapV3Pool::Burn {
+                    owner: Address::ZERO,
+                    tickLower: (-10).try_into().unwrap(),
+                    tickUpper: 10.try_into().unwrap(),
+                    amount: 400,
+                    amount0: Default::default(),
+                    amount1: Default::default(),
+                },
+                2,
+            ),
+            position_log(
+                IUniswapV3Pool::Mint {
+                    sender: Address::ZERO,
+                    owner: Address::ZERO,
+                    tickLower: 20.try_into().unwrap(),
+                    tickUpper: 30.try_into().unwrap(),
+                    amount: 50,
+                    amount0: Default::default(),
+                    amount1: Default::default(),
+                },
+                3,
+            ),
+        ];
+        rebuild_ticks(&mut pool, &base_pool, logs).unwrap();
+
+        // Replaying the logs only updates the ticks, so the in range burn is not subtracted from the populated liquidity
+        assert_eq!(pool.liquidity, 600);
+        assert_eq!(pool.tick, 0);
+        assert_eq!(pool.ticks[&-10].liquidity_gross, 600);
+        assert_eq!(pool.ticks[&10].liquidity_net, -600);
+        assert_eq!(pool.ticks[&20].liquidity_gross, 50);
+        assert_eq!(pool.ticks[&30].liquidity_net, -50);
+
+        // The base is left untouched
+        assert_eq!(base_pool.ticks[&-10].liquidity_gross, 1000);
+    }
+
+    #[tokio::test]
+    async fn test_removed_log_in_same_batch() {
+        let state = Arc::new(LockedStateSpace::from(vec![pool_with_reserve(
+            POOL_ADDRESS,
+            10,
+        )]));
+        let cache = Arc::new(RwLock::new(StateChangeCache::new()));
+
+        // The log is applied and then removed within the same batch
+        let logs = vec![sync_log(2, 20, false), sync_log(2, 20, true)];
+        let events = handle_state_changes_from_logs(state.clone(), cache.clone(), logs)
+            .await
+            .unwrap();
+
+        let Some(AMM::UniswapV2Pool(pool)) = state.get(&POOL_ADDRESS) else {
+            panic!("Unexpected AMM variant");
+        };
+        assert_eq!(pool.reserve_0, 10);
+
+        // The applied and unwound changes are merged into a single change back to the original state
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].changes.len(), 1);
+        let AMM::UniswapV2Pool(pool) = &events[0].changes[0].new else {
+            panic!("Unexpected AMM variant");
+        };
+        assert_eq!(pool.reserve_0, 10);
+
+        // Nothing is left in the cache to unwind for the removed log
+        assert!(cache
+            .write()
+            .await
+            .unwind_state_changes(2)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_unwind_removes_created_amms() {
+        let state = Arc::new(LockedStateSpace::from(vec![
+            pool_with_reserve(POOL_ADDRESS, 2),
+            pool_with_reserve(CREATED_POOL_ADDRESS, 20),
+        ]));
+
+        // The pool changed in block 2, and the created pool was created in block 2 and changed in block 3
+        let mut cache = StateChangeCache::new();
+        cache
+            .add_state_change_to_cache(StateChange::new(
+                vec![pool_with_reserve(POOL_ADDRESS, 1)],
+                2,
+                B256::ZERO,
+            ))
+            .unwrap();
+        cache.add_created_amm(2, CREATED_POOL_ADDRESS);
+        cache
+            .add_state_change_to_cache(StateChange::new(
+                vec![pool_with_reserve(CREATED_POOL_ADDRESS, 10)],
+                3,
+                B256::ZERO,
+            ))
+            .unwrap();
+        let cache = Arc::new(RwLock::new(cache));
+
+        // Unwinding past the creation block restores the created pool and then removes it
+        let events = unwind_state_changes(state.clone(), cache.clone(), 3)
+            .await
+            .unwrap();
+        assert_eq!(events.len(), 1);
+        assert!(state.contains(&CREATED_POOL_ADDRESS));
+
+        let events = unwind_state_changes(state.clone(), cache, 2).await.unwrap();
+        let [StateSpaceEvent::Unwound { changes, .. }, StateSpaceEvent::AmmsRemoved(removed)] =
+            events.as_slice()
+        else {
+            panic!("Unexpected events {events:?}");
+        };
+        assert_eq!(changes.len(), 1);
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].address(), CREATED_POOL_ADDRESS);
+
+        assert!(!state.contains(&CREATED_POOL_ADDRESS));
+        let Some(AMM::UniswapV2Pool(pool)) = state.get(&POOL_ADDRESS) else {
+            panic!("Unexpected AMM variant");
+        };
+        assert_eq!(pool.reserve_0, 1);
+    }
+
+    #[tokio::test]
+    async fn test_replay_recorded_amm_created() {
+        let factory_address = address!("5C69bEe701ef814a2B6a3EDD4B1652CB9cc5aA6f");
+        let token_a = Address::with_last_byte(1);
+        let token_b = Address::with_last_byte(2);
+
+        let pair_created = IUniswapV2Factory::PairCreated {
+            token0: token_a,
+            token1: token_b,
+            pair: CREATED_POOL_ADDRESS,
+            index: U256::ZERO,
+        };
+        let pair_created_log = Log {
+            inner: alloy::primitives::Log {
+                address: factory_address,
+                data: pair_created.encode_log_data(),
+            },
+            block_number: Some(2),
+            ..Default::default()
+        };
+
+        let mut created_sync_log = sync_log(3, 20, false);
+        created_sync_log.inner.address = CREATED_POOL_ADDRESS;
+
+        // The pool as returned by the batch request contract at its creation block
+        let pool_data = DynSolValue::Array(vec![DynSolValue::Tuple(vec![
+            DynSolValue::Address(token_a),
+            DynSolValue::Uint(U256::from(18), 8),
+            DynSolValue::Address(token_b),
+            DynSolValue::Uint(U256::from(6), 8),
+            DynSolValue::Uint(U256::from(10), 112),
+            DynSolValue::Uint(U256::from(10), 112),
+        ])]);
+
+        // Responses to fetching the factory logs, populating the created pool and fetching the logs of the created pool
+        let asserter = Asserter::new();
+        asserter.push_success(&vec![pair_created_log]);
+        asserter.push_success(&Bytes::from(pool_data.abi_encode()));
+        asserter.push_success(&vec![created_sync_log]);
+
+        let provider = Arc::new(
+            ProviderBuilder::new()
+                .disable_recommended_fillers()
+                .connect_mocked_client(asserter),
+        );
+
+        let directory = tempfile::tempdir().unwrap();
+        let manager = StateSpaceManager::new(vec![], provider)
+            .with_recorder(JournalRecorder::new(directory.path()).unwrap());
+        manager.set_filter_mode(LogFilterMode::addresses()).await;
+        manager
+            .set_factories(vec![Factory::UniswapV2Factory(UniswapV2Factory::new(
+                factory_address,
+                0,
+                300,
+            ))])
+            .await;
+
+        let (stream_tx, stream_rx) = tokio::sync::mpsc::channel(1);
+        let (mut subscriber, handle) = manager.subscribe_sync_amms(1, stream_rx, 10).await;
+
+        stream_tx
+            .send(BlockHeader {
+                number: 3,
+                hash: B256::with_last_byte(3),
+                parent_hash: B256::with_last_byte(2),
+            })
+            .await
+            .unwrap();
+        drop(stream_tx);
+
+        let mut events = vec![];
+        while let Some(event) = subscriber.recv().await {
+            events.push(event);
+        }
+        handle.await.unwrap().unwrap();
+
+        let Some(AMM::UniswapV2Pool(synced_pool)) = manager.state().get(&CREATED_POOL_ADDRESS)
+        else {
+            panic!("Unexpected AMM variant");
+        };
+        assert_eq!(synced_pool.reserve_0, 20);
+
+        // Journal records are written on a separate thread
+        let mut records = vec![];
+        for _ in 0..100 {
+            records = read_journal_directory(directory.path()).unwrap();
+            if records.len() == 3 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert!(matches!(
+            records.as_slice(),
+            [
+                JournalRecord::Header(_),
+                JournalRecord::AmmCreated {
+                    block_number: 2,
+                    ..
+                },
+                JournalRecord::Logs { logs, .. },
+            ] if logs.len() == 1
+        ));
+
+        // Replaying the journal adds the created pool without a provider and applies its logs
+        let replay_state = Arc::new(LockedStateSpace::from(vec![]));
+        let mut replay = StateSpaceReplay::new(replay_state.clone(), 10, 1);
+        let replayed_events = replay
+            .replay_journal_directory(directory.path())
+            .await
+            .unwrap();
+
+        assert_eq!(replayed_events.len(), events.len());
+        assert!(matches!(
+            replayed_events.first(),
+            Some(StateSpaceEvent::AmmsCreated(amms)) if amms.len() == 1
+        ));
+
+        let Some(AMM::UniswapV2Pool(replayed_pool)) = replay_state.get(&CREATED_POOL_ADDRESS)
+        else {
+            panic!("Unexpected AMM variant");
+        };
+        assert_eq!(replayed_pool, synced_pool);
+    }
+
+    #[tokio::test]
+    async fn test_skip_synced_first_header() {
+        // Only the logs of the block after the synced block are available
+        let asserter = Asserter::new();
+        asserter.push_success(&vec![sync_log(6, 20, false)]);
+
+        let provider = Arc::new(
+            ProviderBuilder::new()
+                .disable_recommended_fillers()
+                .connect_mocked_client(asserter),
+        );
+        let manager = StateSpaceManager::new(vec![pool_with_reserve(POOL_ADDRESS, 10)], provider);
+
+        let (stream_tx, stream_rx) = tokio::sync::mpsc::channel(2);
+        let (mut subscriber, handle) = manager.subscribe_sync_amms(5, stream_rx, 10).await;
+
+        // The first header is the synced block, which is not in the empty block history
+        for number in [5, 6] {
+            stream_tx
+                .send(BlockHeader {
+                    number,
+                    hash: B256::with_last_byte(number as u8),
+                    parent_hash: B256::with_last_byte(number as u8 - 1),
+                })
+                .await
+                .unwrap();
+        }
+        drop(stream_tx);
+
+        let mut events = vec![];
+        while let Some(event) = subscriber.recv().await {
+            events.push(event);
+        }
+        handle.await.unwrap().unwrap();
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(manager.snapshot().block_number(), 6);
+
+        let Some(AMM::UniswapV2Pool(pool)) = manager.state().get(&POOL_ADDRESS) else {
+            panic!("Unexpected AMM variant");
+        };
+        assert_eq!(pool.reserve_0, 20);
+    }
+
+    #[tokio::test]
+    async fn test_subscriptions_have_separate_broadcasters() {
+        let provider = Arc::new(RootProvider::<Ethereum>::new_http(
+            "http://localhost:8545".parse().unwrap(),
+        ));
+        let manager = StateSpaceManager::new(vec![], provider);
+
+        // Subscribers added before the first subscription starts receive its events
+        let mut early = manager.subscribe(SubscriberPolicy::Block, 1);
+        let first = manager.claim_broadcaster();
+        let second = manager.claim_broadcaster();
+        assert!(!Arc::ptr_eq(&first, &second));
+        assert_eq!(first.subscriber_count(), 1);
+
+        let mut latest = manager.subscribe(SubscriberPolicy::Block, 1);
+        second.send(StateSpaceEvent::AmmsCreated(vec![])).await;
+
+        // Closing the first subscription does not close the subscribers of the second
+        first.close();
+        assert!(early.recv().await.is_none());
+        assert!(latest.recv().await.is_some());
+
+        second.close();
+        assert!(latest.recv().await.is_none());
+        assert!(manager
+            .subscribe(SubscriberPolicy::Block, 1)
+            .recv()
+            .await
+            .is_none());
+    }
+}
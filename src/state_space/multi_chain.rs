@@ -0,0 +1,282 @@
+use std::{collections::HashMap, sync::Arc};
+
+use alloy::{network::Network, providers::Provider};
+use tokio::{
+    sync::mpsc::{Receiver, Sender},
+    task::JoinHandle,
+};
+
+use super::{
+    cache::cache_depth_for_chain,
+    error::StateSpaceError,
+    event::StateSpaceEvent,
+    store::{LockedStateSpace, StateSpaceStore},
+    subscriber::StateSpaceSubscriber,
+    StateSpaceManager,
+};
+use crate::amm::AMM;
+
+/// A state space event tagged with the chain it originated from
+#[derive(Debug, Clone)]
+pub struct ChainEvent {
+    pub chain_id: u64,
+    pub event: StateSpaceEvent,
+}
+
+#[derive(Debug)]
+struct ChainStateSpace<N, P, S> {
+    manager: StateSpaceManager<N, P, S>,
+    /// The block the chain was added with, used until the manager publishes a snapshot at a later block
+    latest_synced_block: u64,
+}
+
+impl<N, P, S> ChainStateSpace<N, P, S>
+where
+    N: Network,
+    P: Provider<N> + 'static,
+    S: StateSpaceStore + 'static,
+{
+    /// Snapshots are published after each block is fully applied, so the latest snapshot is at the latest synced block
+    fn latest_synced_block(&self) -> u64 {
+        self.latest_synced_block
+            .max(self.manager.snapshot().block_number())
+    }
+}
+
+/// Manages a state space per chain, merging the state changes of every chain into a single stream of `ChainEvent`s.
+///
+/// All chains share the same provider type, e.g. a `RootProvider` over a boxed transport.
+#[derive(Debug)]
+pub struct MultiChainStateSpaceManager<N, P, S = LockedStateSpace> {
+    chains: HashMap<u64, ChainStateSpace<N, P, S>>,
+}
+
+impl<N, P> MultiChainStateSpaceManager<N, P>
+where
+    N: Network,
+    P: Provider<N> + 'static,
+{
+    /// Adds a chain using the cache depth preset for the chain id.
+    ///
+    /// The AMMs should be synced through `latest_synced_block`.
+    pub fn add_chain(
+        &mut self,
+        chain_id: u64,
+        amms: Vec<AMM>,
+        latest_synced_block: u64,
+        provider: Arc<P>,
+    ) {
+        self.add_chain_with_cache_depth(
+            chain_id,
+            amms,
+            latest_synced_block,
+            cache_depth_for_chain(chain_id),
+            provider,
+        );
+    }
+
+    /// Adds a chain that retains `cache_depth` blocks of state changes for unwinding reorgs
+    pub fn add_chain_with_cache_depth(
+        &mut self,
+        chain_id: u64,
+        amms: Vec<AMM>,
+        latest_synced_block: u64,
+        cache_depth: usize,
+        provider: Arc<P>,
+    ) {
+        let manager = StateSpaceManager::with_cache_depth(amms, cache_depth, provider);
+        self.insert_chain(chain_id, manager, latest_synced_block);
+    }
+}
+
+impl<N, P, S> MultiChainStateSpaceManager<N, P, S>
+where
+    N: Network,
+    P: Provider<N> + 'static,
+    S: StateSpaceStore + 'static,
+{
+    pub fn new() -> Self {
+        MultiChainStateSpaceManager {
+            chains: HashMap::new(),
+        }
+    }
+
+    /// Adds a chain with a preconfigured state space manager, replacing any existing manager for the chain id
+    pub fn insert_chain(
+        &mut self,
+        chain_id: u64,
+        manager: StateSpaceManager<N, P, S>,
+        latest_synced_block: u64,
+    ) -> Option<StateSpaceManager<N, P, S>> {
+        self.chains
+            .insert(
+                chain_id,
+                ChainStateSpace {
+                    manager,
+                    latest_synced_block,
+                },
+            )
+            .map(|chain| chain.manager)
+    }
+
+    pub fn remove_chain(&mut self, chain_id: u64) -> Option<StateSpaceManager<N, P, S>> {
+        self.chains.remove(&chain_id).map(|chain| chain.manager)
+    }
+
+    /// Returns the state space manager for the chain, which can be used to configure the chain or read its state
+    pub fn chain(&self, chain_id: u64) -> Option<&StateSpaceManager<N, P, S>> {
+        self.chains.get(&chain_id).map(|chain| &chain.manager)
+    }
+
+    pub fn chain_ids(&self) -> Vec<u64> {
+        self.chains.keys().copied().collect()
+    }
+
+    /// Returns the latest block the chain has been synced through
+    pub fn latest_synced_block(&self, chain_id: u64) -> Option<u64> {
+        self.chains
+            .get(&chain_id)
+            .map(ChainStateSpace::latest_synced_block)
+    }
+
+    /// Returns the number of blocks of state changes retained for unwinding reorgs on the chain
+    pub async fn cache_depth(&self, chain_id: u64) -> Option<usize> {
+        Some(self.chain(chain_id)?.cache_depth().await)
+    }
+
+    /// Subscribes to the state changes of every chain, sending each event tagged with its chain id.
+    ///
+    /// Each chain is synced from its latest synced block, so resubscribing resumes from where the previous subscription stopped.
+    /// Returns the join handles of the tasks for each chain. If subscribing to any chain fails, all tasks are aborted.
+    pub async fn subscribe_state_changes(
+        &self,
+        buffer: usize,
+    ) -> Result<
+        (
+            Receiver<ChainEvent>,
+            HashMap<u64, Vec<JoinHandle<Result<(), StateSpaceError>>>>,
+        ),
+        StateSpaceError,
+    > {
+        let (chain_event_tx, chain_event_rx) = tokio::sync::mpsc::channel(buffer);
+
+        let mut join_handles: HashMap<u64, Vec<JoinHandle<Result<(), StateSpaceError>>>> =
+            HashMap::new();
+
+        for (chain_id, chain) in self.chains.iter() {
+            let (state_change_rx, mut handles) = match chain
+                .manager
+                .subscribe_state_changes(chain.latest_synced_block(), buffer)
+                .await
+            {
+                Ok(subscription) => subscription,
+                Err(err) => {
+                    tracing::error!(chain_id, ?err, "failed to subscribe to state changes");
+
+                    join_handles.values().flatten().for_each(JoinHandle::abort);
+                    return Err(err);
+                }
+            };
+
+            handles.push(tokio::spawn(forward_chain_events(
+                *chain_id,
+                state_change_rx,
+                chain_event_tx.clone(),
+            )));
+
+            join_handles.insert(*chain_id, handles);
+        }
+
+        Ok((chain_event_rx, join_handles))
+    }
+}
+
+impl<N, P, S> Default for MultiChainStateSpaceManager<N, P, S>
+where
+    N: Network,
+    P: Provider<N> + 'static,
+    S: StateSpaceStore + 'static,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Tags the state space events of a chain with its chain id and forwards them into the merged stream
+async fn forward_chain_events(
+    chain_id: u64,
+    mut state_change_rx: StateSpaceSubscriber,
+    chain_event_tx: Sender<ChainEvent>,
+) -> Result<(), StateSpaceError> {
+    while let Some(event) = state_change_rx.recv().await {
+        chain_event_tx.send(ChainEvent { chain_id, event }).await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use alloy::{network::Ethereum, providers::RootProvider};
+
+    use super::{forward_chain_events, MultiChainStateSpaceManager};
+    use crate::state_space::{
+        cache::cache_depth_for_chain, event::StateSpaceEvent, subscriber::SubscriberPolicy,
+    };
+
+    #[tokio::test]
+    async fn test_forward_chain_events() {
+        let provider = Arc::new(RootProvider::<Ethereum>::new_http(
+            "http://localhost:8545".parse().unwrap(),
+        ));
+
+        let mut multi_chain = MultiChainStateSpaceManager::new();
+        multi_chain.add_chain(1, vec![], 100, provider.clone());
+        multi_chain.add_chain(137, vec![], 200, provider);
+
+        let (chain_event_tx, mut chain_event_rx) = tokio::sync::mpsc::channel(4);
+        for chain_id in [1, 137] {
+            assert_eq!(
+                multi_chain.cache_depth(chain_id).await,
+                Some(cache_depth_for_chain(chain_id))
+            );
+
+            let manager = multi_chain.chain(chain_id).unwrap();
+            let subscriber = manager.subscribe(SubscriberPolicy::Block, 1);
+            tokio::spawn(forward_chain_events(
+                chain_id,
+                subscriber,
+                chain_event_tx.clone(),
+            ));
+
+            // Tag each event with a block number derived from its chain to check the routing
+            manager
+                .claim_broadcaster()
+                .send(StateSpaceEvent::Unwound {
+                    block_number: chain_id * 1_000,
+                    changes: vec![],
+                })
+                .await;
+        }
+
+        for _ in 0..2 {
+            let chain_event = chain_event_rx.recv().await.unwrap();
+            let StateSpaceEvent::Unwound { block_number, .. } = chain_event.event else {
+                panic!("Unexpected event");
+            };
+            assert_eq!(block_number, chain_event.chain_id * 1_000);
+        }
+
+        // The latest synced block follows the snapshots published by each chain
+        assert_eq!(multi_chain.latest_synced_block(1), Some(100));
+        multi_chain
+            .chain(1)
+            .unwrap()
+            .snapshots
+            .publish_changes(Some(150), &[]);
+        assert_eq!(multi_chain.latest_synced_block(1), Some(150));
+        assert_eq!(multi_chain.latest_synced_block(137), Some(200));
+    }
+}
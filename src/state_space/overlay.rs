@@ -0,0 +1,464 @@
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    sync::{Arc, RwLock},
+};
+
+use alloy::{
+    primitives::{Address, U256},
+    rpc::types::eth::Log,
+};
+
+use super::{error::StateSpaceError, store::StateSpaceStore};
+use crate::amm::{AutomatedMarketMaker, AMM};
+
+/// A hypothetical view of a state space store for simulating pending transactions.
+///
+/// AMMs are copied from the base store the first time they are modified, so the base store is never mutated
+/// until the overlay is committed. Dropping or discarding the overlay leaves the base store untouched.
+/// Since the overlay is itself a `StateSpaceStore`, overlays can be stacked.
+#[derive(Debug)]
+pub struct StateSpaceOverlay<S> {
+    base: Arc<S>,
+    changes: RwLock<HashMap<Address, OverlayEntry>>,
+}
+
+/// An AMM modified in the overlay
+#[derive(Debug)]
+struct OverlayEntry {
+    /// The AMM in the base store when it was copied into the overlay, `None` if it was not in the base store
+    base: Option<AMM>,
+    /// The AMM in the overlay, `None` if it was removed
+    amm: Option<AMM>,
+}
+
+impl<S: StateSpaceStore> StateSpaceOverlay<S> {
+    pub fn new(base: Arc<S>) -> Self {
+        StateSpaceOverlay {
+            base,
+            changes: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub fn base(&self) -> &Arc<S> {
+        &self.base
+    }
+
+    /// Simulates a swap on the AMM within the overlay, returning the amount out
+    pub fn simulate_swap_mut(
+        &self,
+        amm_address: Address,
+        token_in: Address,
+        amount_in: U256,
+    ) -> Result<U256, StateSpaceError> {
+        self.try_update(&amm_address, |amm| {
+            amm.simulate_swap_mut(token_in, amount_in)
+        })
+        .ok_or(StateSpaceError::AmmNotFound(amm_address))?
+        .map_err(StateSpaceError::from)
+    }
+
+    /// Applies a synthetic log to the AMM that emitted it within the overlay.
+    ///
+    /// Returns `false` if the log is not from an AMM in the state space.
+    pub fn apply_log(&self, log: Log) -> Result<bool, StateSpaceError> {
+        let log_address = log.address();
+        match self.try_update(&log_address, |amm| amm.sync_from_log(log)) {
+            Some(result) => result.map(|_| true).map_err(StateSpaceError::from),
+            None => Ok(false),
+        }
+    }
+
+    /// Returns the addresses of the AMMs modified or removed in the overlay
+    pub fn changed_addresses(&self) -> Vec<Address> {
+        self.changes.read().unwrap().keys().copied().collect()
+    }
+
+    /// Applies `f` to a copy of the AMM, only writing the copy to the overlay if `f` succeeds
+    fn try_update<T, E>(
+        &self,
+        address: &Address,
+        f: impl FnOnce(&mut AMM) -> Result<T, E>,
+    ) -> Option<Result<T, E>> {
+        let mut changes = self.changes.write().unwrap();
+
+        let (base, mut amm) = match changes.get(address) {
+            Some(entry) => (None, entry.amm.clone()?),
+            None => {
+                let amm = self.base.get(address)?;
+                (Some(amm.clone()), amm)
+            }
+        };
+
+        let result = f(&mut amm);
+        if result.is_ok() {
+            changes
+                .entry(*address)
+                .or_insert(OverlayEntry { base, amm: None })
+                .amm = Some(amm);
+        }
+
+        Some(result)
+    }
+
+    /// Returns the overlay entry of the AMM, copying the AMM from the base store if it has not been modified yet
+    fn entry<'a>(
+        &self,
+        changes: &'a mut HashMap<Address, OverlayEntry>,
+        address: Address,
+    ) -> &'a mut OverlayEntry {
+        changes.entry(address).or_insert_with(|| {
+            let base = self.base.get(&address);
+            OverlayEntry {
+                amm: base.clone(),
+                base,
+            }
+        })
+    }
+
+    /// Replaces AMMs changed in the overlay within an index lookup on the base store
+    fn overlay_index_lookup(
+        &self,
+        base_addresses: Vec<Address>,
+        matches: impl Fn(&[Address]) -> bool,
+    ) -> Vec<Address> {
+        let changes = self.changes.read().unwrap();
+
+        let mut addresses = base_addresses
+            .into_iter()
+            .filter(|address| !changes.contains_key(address))
+            .collect::<Vec<Address>>();
+
+        addresses.extend(
+            changes
+                .values()
+                .filter_map(|entry| entry.amm.as_ref())
+                .filter(|amm| matches(&amm.tokens()))
+                .map(|amm| amm.address()),
+        );
+
+        addresses
+    }
+
+    /// Drops the overlay without modifying the base store
+    pub fn discard(self) {}
+
+    /// Writes the AMMs modified in the overlay to the base store, returning the addresses of the AMMs that were written or removed.
+    ///
+    /// AMMs are only written if the base store still holds the AMM that was copied into the overlay, so state applied to the
+    /// base store in the meantime is never overwritten. If any AMM has changed before committing, nothing is written and
+    /// `StateSpaceError::OverlayConflict` is returned. Since each AMM is swapped separately, an AMM changed concurrently while
+    /// committing is skipped while the other AMMs are still written, so only the returned addresses are committed.
+    /// Use `StateSpaceManager::commit_overlay` to also publish a snapshot.
+    pub fn commit(self) -> Result<Vec<Address>, StateSpaceError> {
+        let changes = self
+            .changes
+            .into_inner()
+            .unwrap()
+            .into_iter()
+            .filter(|(_, entry)| !same_state(entry.base.as_ref(), entry.amm.as_ref()))
+            .collect::<Vec<_>>();
+
+        let conflicts = changes
+            .iter()
+            .filter(|(address, entry)| {
+                !same_state(self.base.get(address).as_ref(), entry.base.as_ref())
+            })
+            .map(|(address, _)| *address)
+            .collect::<Vec<Address>>();
+        if !conflicts.is_empty() {
+            return Err(StateSpaceError::OverlayConflict(conflicts));
+        }
+
+        let mut committed = Vec::with_capacity(changes.len());
+        for (address, entry) in changes {
+            // Compare again when writing, since the base store may be updated concurrently
+            if self
+                .base
+                .compare_and_swap(&address, entry.base.as_ref(), entry.amm)
+            {
+                committed.push(address);
+            } else {
+                tracing::warn!(?address, "AMM changed while committing the overlay");
+            }
+        }
+
+        Ok(committed)
+    }
+}
+
+impl<S: StateSpaceStore> StateSpaceStore for StateSpaceOverlay<S> {
+    fn get(&self, address: &Address) -> Option<AMM> {
+        match self.changes.read().unwrap().get(address) {
+            Some(entry) => entry.amm.clone(),
+            None => self.base.get(address),
+        }
+    }
+
+    fn contains(&self, address: &Address) -> bool {
+        match self.changes.read().unwrap().get(address) {
+            Some(entry) => entry.amm.is_some(),
+            None => self.base.contains(address),
+        }
+    }
+
+    fn insert(&self, amm: AMM) -> Option<AMM> {
+        let mut changes = self.changes.write().unwrap();
+        self.entry(&mut changes, amm.address()).amm.replace(amm)
+    }
+
+    fn remove(&self, address: &Address) -> Option<AMM> {
+        let mut changes = self.changes.write().unwrap();
+
+        let entry = self.entry(&mut changes, *address);
+        let prev_amm = entry.amm.take();
+
+        // AMMs that are not in the base store do not need to be tracked once removed
+        if entry.base.is_none() {
+            changes.remove(address);
+        }
+
+        prev_amm
+    }
+
+    fn replace(&self, amm: AMM) -> Option<AMM> {
+        let mut changes = self.changes.write().unwrap();
+
+        let address = amm.address();
+        let contains = match changes.get(&address) {
+            Some(entry) => entry.amm.is_some(),
+            None => self.base.contains(&address),
+        };
+        if !contains {
+            return None;
+        }
+
+        self.entry(&mut changes, address).amm.replace(amm)
+    }
+
+    fn update<R>(&self, address: &Address, f: impl FnOnce(&mut AMM) -> R) -> Option<R> {
+        self.try_update(address, |amm| Ok::<R, Infallible>(f(amm)))
+            .map(|result| result.unwrap_or_else(|never| match never {}))
+    }
+
+    fn compare_and_swap(&self, address: &Address, current: Option<&AMM>, new: Option<AMM>) -> bool {
+        let mut changes = self.changes.write().unwrap();
+
+        // Compare before copying the AMM, so that a failed swap does not copy it into the overlay
+        let matches = match changes.get(address) {
+            Some(entry) => same_state(entry.amm.as_ref(), current),
+            None => same_state(self.base.get(address).as_ref(), current),
+        };
+        if !matches {
+            return false;
+        }
+
+        let entry = self.entry(&mut changes, *address);
+        entry.amm = new;
+
+        // AMMs that are not in the base store do not need to be tracked once removed
+        if entry.base.is_none() && entry.amm.is_none() {
+            changes.remove(address);
+        }
+
+        true
+    }
+
+    fn for_each(&self, mut f: impl FnMut(&AMM)) {
+        let changes = self.changes.read().unwrap();
+
+        self.base.for_each(|amm| {
+            if !changes.contains_key(&amm.address()) {
+                f(amm);
+            }
+        });
+
+        changes
+            .values()
+            .filter_map(|entry| entry.amm.as_ref())
+            .for_each(f);
+    }
+
+    fn len(&self) -> usize {
+        let changes = self.changes.read().unwrap();
+
+        let mut len = self.base.len();
+        for (address, entry) in changes.iter() {
+            match (self.base.contains(address), entry.amm.is_some()) {
+                (false, true) => len += 1,
+                (true, false) => len -= 1,
+                _ => {}
+            }
+        }
+        len
+    }
+
+    fn amms_containing(&self, token: &Address) -> Vec<Address> {
+        self.overlay_index_lookup(self.base.amms_containing(token), |tokens| {
+            tokens.contains(token)
+        })
+    }
+
+    fn amms_for_pair(&self, token_a: &Address, token_b: &Address) -> Vec<Address> {
+        self.overlay_index_lookup(self.base.amms_for_pair(token_a, token_b), |tokens| {
+            tokens.contains(token_a) && tokens.contains(token_b)
+        })
+    }
+}
+
+/// Returns true if the AMMs hold the same state, since `AMM` equality only compares addresses
+pub(crate) fn same_state(a: Option<&AMM>, b: Option<&AMM>) -> bool {
+    match (a, b) {
+        (Some(AMM::UniswapV2Pool(a)), Some(AMM::UniswapV2Pool(b))) => a == b,
+        (Some(AMM::UniswapV3Pool(a)), Some(AMM::UniswapV3Pool(b))) => a == b,
+        (Some(AMM::ERC4626Vault(a)), Some(AMM::ERC4626Vault(b))) => a == b,
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use alloy::{
+        primitives::{address, Address, Bytes, B256, U256},
+        rpc::types::eth::Log,
+    };
+
+    use super::StateSpaceOverlay;
+    use crate::{
+        amm::{uniswap_v2::UniswapV2Pool, AMM},
+        state_space::{
+            error::StateSpaceError,
+            store::{LockedStateSpace, StateSpaceStore},
+        },
+    };
+
+    const POOL_ADDRESS: Address = address!("B4e16d0168e52d35CaCD2c6185b44281Ec28C9Dc");
+    const TOKEN_A: Address = address!("A0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48");
+
+    fn reserve_0(store: &impl StateSpaceStore) -> u128 {
+        match store.get(&POOL_ADDRESS) {
+            Some(AMM::UniswapV2Pool(pool)) => pool.reserve_0,
+            _ => panic!("Unexpected AMM variant"),
+        }
+    }
+
+    fn base() -> Arc<LockedStateSpace> {
+        Arc::new(LockedStateSpace::from(vec![AMM::UniswapV2Pool(
+            UniswapV2Pool {
+                address: POOL_ADDRESS,
+                token_a: TOKEN_A,
+                token_b: address!("C02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"),
+                reserve_0: 1_000_000,
+                reserve_1: 1_000_000,
+                fee: 300,
+                ..Default::default()
+            },
+        )]))
+    }
+
+    #[test]
+    fn test_overlay_is_copy_on_write() {
+        let base = base();
+        let overlay = StateSpaceOverlay::new(base.clone());
+
+        overlay
+            .simulate_swap_mut(POOL_ADDRESS, TOKEN_A, U256::from(1_000))
+            .unwrap();
+
+        assert_eq!(reserve_0(&overlay), 1_001_000);
+        assert_eq!(reserve_0(base.as_ref()), 1_000_000);
+
+        overlay.discard();
+        assert_eq!(reserve_0(base.as_ref()), 1_000_000);
+    }
+
+    #[test]
+    fn test_overlay_commit() {
+        let base = base();
+        let overlay = StateSpaceOverlay::new(base.clone());
+
+        overlay
+            .simulate_swap_mut(POOL_ADDRESS, TOKEN_A, U256::from(1_000))
+            .unwrap();
+        assert_eq!(overlay.commit().unwrap(), vec![POOL_ADDRESS]);
+        assert_eq!(reserve_0(base.as_ref()), 1_001_000);
+
+        // Removals are only visible through the overlay until committed
+        let overlay = StateSpaceOverlay::new(base.clone());
+        overlay.remove(&POOL_ADDRESS);
+        assert!(overlay.is_empty());
+        assert_eq!(base.len(), 1);
+
+        overlay.commit().unwrap();
+        assert!(base.is_empty());
+    }
+
+    #[test]
+    fn test_overlay_commit_conflict() {
+        let base = base();
+        let overlay = StateSpaceOverlay::new(base.clone());
+
+        overlay
+            .simulate_swap_mut(POOL_ADDRESS, TOKEN_A, U256::from(1_000))
+            .unwrap();
+
+        // A newer update to the base store after the AMM was copied must not be overwritten
+        base.update(&POOL_ADDRESS, |amm| {
+            if let AMM::UniswapV2Pool(pool) = amm {
+                pool.reserve_0 = 2_000_000;
+            }
+        });
+
+        assert!(matches!(
+            overlay.commit(),
+            Err(StateSpaceError::OverlayConflict(conflicts)) if conflicts == vec![POOL_ADDRESS]
+        ));
+        assert_eq!(reserve_0(base.as_ref()), 2_000_000);
+    }
+
+    #[test]
+    fn test_failed_update_is_not_copied() {
+        let overlay = StateSpaceOverlay::new(base());
+
+        let log = Log {
+            inner: alloy::primitives::Log::new_unchecked(
+                POOL_ADDRESS,
+                vec![B256::ZERO],
+                Bytes::new(),
+            ),
+            ..Default::default()
+        };
+
+        assert!(overlay.apply_log(log).is_err());
+        assert!(overlay.changed_addresses().is_empty());
+    }
+
+    #[test]
+    fn test_overlay_commit_does_not_overwrite_inserted_amm() {
+        let base = Arc::new(LockedStateSpace::new());
+        let overlay = StateSpaceOverlay::new(base.clone());
+
+        let amm = AMM::UniswapV2Pool(UniswapV2Pool {
+            address: POOL_ADDRESS,
+            reserve_0: 1,
+            ..Default::default()
+        });
+        overlay.insert(amm);
+
+        // The AMM is inserted into the base store after the overlay, so the commit conflicts instead of overwriting it
+        base.insert(AMM::UniswapV2Pool(UniswapV2Pool {
+            address: POOL_ADDRESS,
+            reserve_0: 2,
+            ..Default::default()
+        }));
+
+        assert!(matches!(
+            overlay.commit(),
+            Err(StateSpaceError::OverlayConflict(conflicts)) if conflicts == vec![POOL_ADDRESS]
+        ));
+        assert_eq!(reserve_0(base.as_ref()), 2);
+    }
+}
@@ -0,0 +1,256 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::mpsc,
+};
+
+use super::{
+    error::StateSpaceError,
+    journal::{read_journal, JournalRecord},
+};
+
+/// Prefix and extension of the journal files written by `JournalRecorder`
+const JOURNAL_FILE_PREFIX: &str = "journal-";
+const JOURNAL_FILE_EXTENSION: &str = "jsonl";
+
+/// Default size in bytes after which a new journal file is started
+pub const DEFAULT_MAX_FILE_SIZE: u64 = 64 * 1024 * 1024;
+/// Default number of journal files retained in the directory
+pub const DEFAULT_MAX_FILES: usize = 16;
+
+/// Records the input received by the state space to a rotating journal on disk.
+///
+/// Records are written to `journal-<index>.jsonl` files in the journal directory, in the format documented on `JournalRecord`.
+/// Once the current file exceeds `max_file_size`, the next chain head starts a new file with the next index,
+/// so a chain head is never split from its ancestors and logs. Only the newest `max_files` files are retained.
+#[derive(Debug)]
+pub struct JournalRecorder {
+    directory: PathBuf,
+    max_file_size: u64,
+    max_files: usize,
+    file: BufWriter<File>,
+    file_index: u64,
+    file_size: u64,
+}
+
+impl JournalRecorder {
+    /// Creates a recorder with the default rotation limits
+    pub fn new<P: AsRef<Path>>(directory: P) -> Result<Self, StateSpaceError> {
+        Self::with_rotation(directory, DEFAULT_MAX_FILE_SIZE, DEFAULT_MAX_FILES)
+    }
+
+    /// Creates a recorder that starts a new journal file after `max_file_size` bytes and retains at most `max_files` files.
+    ///
+    /// The directory is created if it does not exist. Existing journal files are kept and recording continues in a new file.
+    pub fn with_rotation<P: AsRef<Path>>(
+        directory: P,
+        max_file_size: u64,
+        max_files: usize,
+    ) -> Result<Self, StateSpaceError> {
+        assert!(max_files > 0, "Journal must retain at least one file");
+
+        let directory = directory.as_ref().to_path_buf();
+        std::fs::create_dir_all(&directory)?;
+
+        let file_index = journal_files(&directory)?
+            .last()
+            .and_then(|path| journal_file_index(path))
+            .map_or(0, |index| index + 1);
+
+        let recorder = JournalRecorder {
+            file: open_journal_file(&directory, file_index)?,
+            directory,
+            max_file_size,
+            max_files,
+            file_index,
+            file_size: 0,
+        };
+        recorder.remove_old_files()?;
+
+        Ok(recorder)
+    }
+
+    /// Returns the directory the journal is written to
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// Appends the record to the journal, flushing it to disk so the journal is complete up to the latest record
+    pub fn record(&mut self, record: &JournalRecord) -> Result<(), StateSpaceError> {
+        self.record_all(std::slice::from_ref(record))
+    }
+
+    /// Appends the records to the journal, flushing them to disk together
+    pub fn record_all(&mut self, records: &[JournalRecord]) -> Result<(), StateSpaceError> {
+        for record in records {
+            if matches!(record, JournalRecord::Header(_)) && self.file_size >= self.max_file_size {
+                self.rotate()?;
+            }
+
+            let mut line = serde_json::to_vec(record)?;
+            line.push(b'\n');
+
+            self.file.write_all(&line)?;
+            self.file_size += line.len() as u64;
+        }
+
+        self.file.flush()?;
+
+        Ok(())
+    }
+
+    /// Starts a new journal file, removing the oldest files beyond `max_files`
+    fn rotate(&mut self) -> Result<(), StateSpaceError> {
+        self.file.flush()?;
+
+        self.file_index += 1;
+        self.file = open_journal_file(&self.directory, self.file_index)?;
+        self.file_size = 0;
+
+        self.remove_old_files()
+    }
+
+    fn remove_old_files(&self) -> Result<(), StateSpaceError> {
+        let files = journal_files(&self.directory)?;
+        for path in files
+            .iter()
+            .take(files.len().saturating_sub(self.max_files))
+        {
+            std::fs::remove_file(path)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes records to a `JournalRecorder` on a dedicated thread, so that file writes never block the async runtime.
+///
+/// If a write fails, the error is logged and the recorder is disabled, so journal errors never interrupt syncing.
+#[derive(Debug, Clone)]
+pub(crate) struct JournalWriter(mpsc::Sender<Vec<JournalRecord>>);
+
+impl JournalWriter {
+    pub(crate) fn spawn(mut recorder: JournalRecorder) -> Self {
+        let (records_tx, records_rx) = mpsc::channel::<Vec<JournalRecord>>();
+
+        std::thread::spawn(move || {
+            for records in records_rx {
+                if let Err(err) = recorder.record_all(&records) {
+                    tracing::error!(
+                        ?err,
+                        directory = ?recorder.directory(),
+                        "failed to write to the journal, disabling the recorder"
+                    );
+                    return;
+                }
+            }
+        });
+
+        JournalWriter(records_tx)
+    }
+
+    /// Queues the records to be written together, dropping them if the recorder has been disabled
+    pub(crate) fn record(&self, records: Vec<JournalRecord>) {
+        if !records.is_empty() {
+            let _ = self.0.send(records);
+        }
+    }
+}
+
+/// Returns the journal files in the directory, oldest first
+pub fn journal_files<P: AsRef<Path>>(directory: P) -> Result<Vec<PathBuf>, StateSpaceError> {
+    let mut files = vec![];
+    for entry in std::fs::read_dir(directory)? {
+        let path = entry?.path();
+        if let Some(index) = journal_file_index(&path) {
+            files.push((index, path));
+        }
+    }
+
+    files.sort();
+
+    Ok(files.into_iter().map(|(_, path)| path).collect())
+}
+
+/// Reads every record from the journal files in the directory, oldest first
+pub fn read_journal_directory<P: AsRef<Path>>(
+    directory: P,
+) -> Result<Vec<JournalRecord>, StateSpaceError> {
+    let mut records = vec![];
+    for path in journal_files(directory)? {
+        records.extend(read_journal(path)?);
+    }
+
+    Ok(records)
+}
+
+fn open_journal_file(directory: &Path, index: u64) -> Result<BufWriter<File>, StateSpaceError> {
+    let path = directory.join(format!(
+        "{JOURNAL_FILE_PREFIX}{index:020}.{JOURNAL_FILE_EXTENSION}"
+    ));
+
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+
+    Ok(BufWriter::new(file))
+}
+
+/// Parses the index from the name of a journal file, returning `None` for any other file
+fn journal_file_index(path: &Path) -> Option<u64> {
+    if path.extension()? != JOURNAL_FILE_EXTENSION {
+        return None;
+    }
+
+    path.file_stem()?
+        .to_str()?
+        .strip_prefix(JOURNAL_FILE_PREFIX)?
+        .parse()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::B256;
+
+    use super::{journal_files, read_journal_directory, JournalRecorder};
+    use crate::state_space::journal::{BlockHeader, JournalRecord};
+
+    #[test]
+    fn test_journal_rotation() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let directory = temp_dir.path().join("journal");
+
+        let records = (1..=6)
+            .flat_map(|number| {
+                [
+                    JournalRecord::Header(BlockHeader {
+                        number,
+                        hash: B256::with_last_byte(number as u8),
+                        parent_hash: B256::with_last_byte(number as u8 - 1),
+                    }),
+                    JournalRecord::Logs {
+                        from_block: number,
+                        to_block: number,
+                        logs: vec![],
+                    },
+                ]
+            })
+            .collect::<Vec<_>>();
+
+        // Rotate on every chain head, retaining the newest three files
+        let mut recorder = JournalRecorder::with_rotation(&directory, 1, 3).unwrap();
+        for record in records.iter() {
+            recorder.record(record).unwrap();
+        }
+
+        assert_eq!(journal_files(&directory).unwrap().len(), 3);
+        assert_eq!(read_journal_directory(&directory).unwrap(), records[6..]);
+
+        // Recording resumes in a new file when the recorder is recreated
+        drop(recorder);
+        JournalRecorder::with_rotation(&directory, 1, 3).unwrap();
+        let files = journal_files(&directory).unwrap();
+        assert_eq!(files.len(), 3);
+        assert!(files[2].ends_with("journal-00000000000000000006.jsonl"));
+    }
+}
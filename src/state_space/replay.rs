@@ -0,0 +1,355 @@
+use std::{collections::HashMap, path::Path, sync::Arc};
+
+use alloy::{primitives::B256, rpc::types::eth::Log};
+use tokio::sync::RwLock;
+
+use super::{
+    cache::StateChangeCache,
+    error::StateSpaceError,
+    event::StateSpaceEvent,
+    find_common_ancestor, handle_state_changes_from_logs,
+    journal::{read_journal, BlockHeader, JournalRecord},
+    recorder::read_journal_directory,
+    store::StateSpaceStore,
+    unwind_state_changes,
+};
+use crate::amm::{AutomatedMarketMaker, AMM};
+
+/// Replays a recorded journal through the state space without a provider.
+///
+/// Headers, ancestors and logs are applied in the order they were recorded, running the same reorg detection
+/// and unwinding as a live subscription. AMMs created by tracked factories are added from their recorded state before the logs of
+/// their block are applied, since populating them requires a provider.
+///
+/// For the same reason, a reorg deeper than the state change cache can not be replayed. A live subscription repopulates
+/// every AMM from the provider after such a reorg, while a replay returns `StateSpaceError::ReorgTooDeep`.
+#[derive(Debug)]
+pub struct StateSpaceReplay<S> {
+    state: Arc<S>,
+    state_change_cache: Arc<RwLock<StateChangeCache>>,
+    latest_synced_block: u64,
+    /// Every replayed header by hash, used in place of a provider when walking back to find a common ancestor
+    headers: HashMap<B256, BlockHeader>,
+    /// The latest chain head, applied once its logs are replayed
+    pending_header: Option<BlockHeader>,
+    /// AMMs created in the blocks of the pending chain head, along with the block each was populated at
+    pending_amms_created: Vec<(AMM, u64)>,
+}
+
+impl<S: StateSpaceStore> StateSpaceReplay<S> {
+    /// Creates a replay of a state space synced through `latest_synced_block`
+    pub fn new(state: Arc<S>, cache_depth: usize, latest_synced_block: u64) -> Self {
+        Self::with_cache(
+            state,
+            Arc::new(RwLock::new(StateChangeCache::with_depth(cache_depth))),
+            latest_synced_block,
+        )
+    }
+
+    pub(crate) fn with_cache(
+        state: Arc<S>,
+        state_change_cache: Arc<RwLock<StateChangeCache>>,
+        latest_synced_block: u64,
+    ) -> Self {
+        StateSpaceReplay {
+            state,
+            state_change_cache,
+            latest_synced_block,
+            headers: HashMap::new(),
+            pending_header: None,
+            pending_amms_created: vec![],
+        }
+    }
+
+    pub fn state(&self) -> Arc<S> {
+        self.state.clone()
+    }
+
+    pub fn latest_synced_block(&self) -> u64 {
+        self.latest_synced_block
+    }
+
+    /// Replays every record in the journal, returning the events that a live subscription would have emitted
+    pub async fn replay_journal<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+    ) -> Result<Vec<StateSpaceEvent>, StateSpaceError> {
+        self.replay_records(read_journal(path)?).await
+    }
+
+    /// Replays every record in the journal files written by a `JournalRecorder` to the directory, oldest first
+    pub async fn replay_journal_directory<P: AsRef<Path>>(
+        &mut self,
+        directory: P,
+    ) -> Result<Vec<StateSpaceEvent>, StateSpaceError> {
+        self.replay_records(read_journal_directory(directory)?)
+            .await
+    }
+
+    /// Replays the records, applying the last chain head once all records are replayed
+    pub async fn replay_records(
+        &mut self,
+        records: Vec<JournalRecord>,
+    ) -> Result<Vec<StateSpaceEvent>, StateSpaceError> {
+        let mut events = vec![];
+        for record in records {
+            events.extend(self.apply_record(record).await?);
+        }
+
+        events.extend(self.flush().await?);
+
+        Ok(events)
+    }
+
+    /// Applies a single record.
+    ///
+    /// Chain heads are applied once their logs or the next chain head are replayed, since the ancestors
+    /// needed to detect a reorg are recorded after the chain head.
+    pub async fn apply_record(
+        &mut self,
+        record: JournalRecord,
+    ) -> Result<Vec<StateSpaceEvent>, StateSpaceError> {
+        match record {
+            JournalRecord::Header(header) => {
+                let events = self.flush().await?;
+
+                self.headers.insert(header.hash, header);
+                self.pending_header = Some(header);
+
+                Ok(events)
+            }
+            JournalRecord::Ancestor(header) => {
+                self.headers.insert(header.hash, header);
+                Ok(vec![])
+            }
+            JournalRecord::AmmCreated { block_number, amm } => {
+                self.pending_amms_created.push((*amm, block_number));
+                Ok(vec![])
+            }
+            JournalRecord::Logs { logs, .. } => match self.pending_header.take() {
+                Some(header) => self.apply_block(header, logs).await,
+                // Logs recorded without a chain head are applied directly
+                None => {
+                    let mut events = self.add_amms_created().await;
+                    events.extend(self.apply_logs(logs).await?);
+                    Ok(events)
+                }
+            },
+        }
+    }
+
+    /// Applies the pending chain head and created AMMs without any logs
+    pub async fn flush(&mut self) -> Result<Vec<StateSpaceEvent>, StateSpaceError> {
+        match self.pending_header.take() {
+            Some(header) => self.apply_block(header, vec![]).await,
+            None => Ok(self.add_amms_created().await),
+        }
+    }
+
+    async fn apply_block(
+        &mut self,
+        header: BlockHeader,
+        logs: Vec<Log>,
+    ) -> Result<Vec<StateSpaceEvent>, StateSpaceError> {
+        // Skip blocks that have already been synced, including blocks up to the latest synced block that predate the block history
+        let cached_block_hash = self
+            .state_change_cache
+            .read()
+            .await
+            .block_hash(header.number);
+        if cached_block_hash == Some(header.hash)
+            || (cached_block_hash.is_none() && header.number <= self.latest_synced_block)
+        {
+            self.pending_amms_created.clear();
+            return Ok(vec![]);
+        }
+
+        let mut events = vec![];
+
+        let headers = &self.headers;
+        let (common_ancestor, canonical_blocks) =
+            find_common_ancestor(self.state_change_cache.clone(), &header, |block_hash| {
+                std::future::ready(Ok(headers.get(&block_hash).copied()))
+            })
+            .await?;
+
+        // If the common ancestor is behind the latest synced block, a reorg has occurred
+        if common_ancestor < self.latest_synced_block {
+            events.extend(
+                unwind_state_changes(
+                    self.state.clone(),
+                    self.state_change_cache.clone(),
+                    common_ancestor + 1,
+                )
+                .await?,
+            );
+            self.latest_synced_block = common_ancestor;
+        }
+
+        // Created AMMs are added after unwinding, so that logs after their creation block are applied to them
+        events.extend(self.add_amms_created().await);
+        events.extend(self.apply_logs(logs).await?);
+
+        let mut cache = self.state_change_cache.write().await;
+        for (block_number, block_hash) in canonical_blocks {
+            cache.add_block_hash(block_number, block_hash);
+        }
+        drop(cache);
+
+        self.latest_synced_block = header.number;
+
+        Ok(events)
+    }
+
+    /// Adds the pending created AMMs that are not already tracked, recording their creation in the state change cache
+    /// so that they are removed if their block is unwound
+    async fn add_amms_created(&mut self) -> Vec<StateSpaceEvent> {
+        let mut amms_created = vec![];
+
+        let mut cache = self.state_change_cache.write().await;
+        for (amm, block_number) in std::mem::take(&mut self.pending_amms_created) {
+            if self.state.contains(&amm.address()) {
+                continue;
+            }
+
+            self.state.insert(amm.clone());
+            cache.add_created_amm(block_number, amm.address());
+            amms_created.push(amm);
+        }
+        drop(cache);
+
+        if amms_created.is_empty() {
+            vec![]
+        } else {
+            vec![StateSpaceEvent::AmmsCreated(amms_created)]
+        }
+    }
+
+    async fn apply_logs(
+        &mut self,
+        logs: Vec<Log>,
+    ) -> Result<Vec<StateSpaceEvent>, StateSpaceError> {
+        let state_change_events = handle_state_changes_from_logs(
+            self.state.clone(),
+            self.state_change_cache.clone(),
+            logs,
+        )
+        .await?;
+
+        Ok(state_change_events
+            .into_iter()
+            .map(StateSpaceEvent::StateChanged)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use alloy::{
+        primitives::{address, Address, B256},
+        rpc::types::eth::Log,
+        sol_types::SolEvent,
+    };
+
+    use super::StateSpaceReplay;
+    use crate::{
+        amm::{
+            uniswap_v2::{IUniswapV2Pair, UniswapV2Pool},
+            AMM,
+        },
+        state_space::{
+            event::StateSpaceEvent,
+            journal::{read_journal, BlockHeader, JournalRecord},
+            store::{LockedStateSpace, StateSpaceStore},
+        },
+    };
+
+    const POOL_ADDRESS: Address = address!("B4e16d0168e52d35CaCD2c6185b44281Ec28C9Dc");
+
+    fn header(number: u64, hash: u8, parent_hash: u8) -> BlockHeader {
+        BlockHeader {
+            number,
+            hash: B256::with_last_byte(hash),
+            parent_hash: B256::with_last_byte(parent_hash),
+        }
+    }
+
+    fn sync_logs(header: &BlockHeader, reserve: u128) -> JournalRecord {
+        let sync = IUniswapV2Pair::Sync {
+            reserve0: reserve.try_into().unwrap(),
+            reserve1: reserve.try_into().unwrap(),
+        };
+
+        let log = Log {
+            inner: alloy::primitives::Log {
+                address: POOL_ADDRESS,
+                data: sync.encode_log_data(),
+            },
+            block_number: Some(header.number),
+            block_hash: Some(header.hash),
+            ..Default::default()
+        };
+
+        JournalRecord::Logs {
+            from_block: header.number,
+            to_block: header.number,
+            logs: vec![log],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replay_journal_with_reorg() {
+        let block_1 = header(1, 1, 0);
+        let block_2 = header(2, 2, 1);
+        // Block 2 is reorged out by a fork starting from block 1
+        let fork_block_2 = header(2, 20, 1);
+        let fork_block_3 = header(3, 30, 20);
+
+        let records = vec![
+            JournalRecord::Header(block_1),
+            sync_logs(&block_1, 10),
+            JournalRecord::Header(block_2),
+            sync_logs(&block_2, 20),
+            JournalRecord::Header(fork_block_3),
+            JournalRecord::Ancestor(fork_block_2),
+            sync_logs(&fork_block_3, 30),
+        ];
+
+        // Round trip the records through a journal on disk
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("journal.jsonl");
+        let journal = records
+            .iter()
+            .map(|record| serde_json::to_string(record).unwrap())
+            .collect::<Vec<String>>()
+            .join("\n");
+        std::fs::write(&path, journal).unwrap();
+        assert_eq!(read_journal(&path).unwrap(), records);
+
+        let state = Arc::new(LockedStateSpace::from(vec![AMM::UniswapV2Pool(
+            UniswapV2Pool {
+                address: POOL_ADDRESS,
+                ..Default::default()
+            },
+        )]));
+
+        let mut replay = StateSpaceReplay::new(state.clone(), 10, 0);
+        let events = replay.replay_journal(&path).await.unwrap();
+
+        assert_eq!(replay.latest_synced_block(), 3);
+        assert!(events.iter().any(|event| matches!(
+            event,
+            StateSpaceEvent::Unwound {
+                block_number: 2,
+                ..
+            }
+        )));
+
+        let Some(AMM::UniswapV2Pool(pool)) = state.get(&POOL_ADDRESS) else {
+            panic!("Unexpected AMM variant");
+        };
+        assert_eq!(pool.reserve_0, 30);
+    }
+}
@@ -0,0 +1,199 @@
+use std::sync::Arc;
+
+use alloy::primitives::Address;
+use arc_swap::ArcSwap;
+use im::HashMap;
+
+use super::index::TokenIndex;
+use crate::amm::{AutomatedMarketMaker, AMM};
+
+/// An immutable view of every AMM in the state space after a block has been fully applied.
+///
+/// The AMMs and token index are stored in persistent maps, so consecutive snapshots share everything but the changed AMMs.
+#[derive(Debug, Clone, Default)]
+pub struct StateSpaceSnapshot {
+    block_number: u64,
+    amms: HashMap<Address, Arc<AMM>>,
+    index: TokenIndex,
+}
+
+impl StateSpaceSnapshot {
+    pub fn new(block_number: u64, amms: Vec<AMM>) -> Self {
+        StateSpaceSnapshot {
+            block_number,
+            index: amms.iter().cloned().collect(),
+            amms: amms
+                .into_iter()
+                .map(|amm| (amm.address(), Arc::new(amm)))
+                .collect(),
+        }
+    }
+
+    /// The block that the snapshot reflects the state of
+    pub fn block_number(&self) -> u64 {
+        self.block_number
+    }
+
+    pub fn get(&self, address: &Address) -> Option<&AMM> {
+        self.amms.get(address).map(Arc::as_ref)
+    }
+
+    pub fn contains(&self, address: &Address) -> bool {
+        self.amms.contains_key(address)
+    }
+
+    pub fn len(&self) -> usize {
+        self.amms.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.amms.is_empty()
+    }
+
+    pub fn amms(&self) -> impl Iterator<Item = &AMM> {
+        self.amms.values().map(Arc::as_ref)
+    }
+
+    /// Returns the addresses of the AMMs containing the token
+    pub fn amms_containing(&self, token: &Address) -> Vec<Address> {
+        self.index.amms_containing(token)
+    }
+
+    /// Returns the addresses of the AMMs containing both tokens, regardless of order
+    pub fn amms_for_pair(&self, token_a: &Address, token_b: &Address) -> Vec<Address> {
+        self.index.amms_for_pair(token_a, token_b)
+    }
+
+    /// Returns a new snapshot at `block_number` with the changed AMMs applied, sharing unchanged AMMs with this snapshot.
+    /// Building the snapshot costs O(changes), independent of the number of AMMs in the state space.
+    ///
+    /// AMMs changed to `None` are removed from the new snapshot.
+    pub fn with_changes(&self, block_number: u64, changes: &[(Address, Option<AMM>)]) -> Self {
+        let mut amms = self.amms.clone();
+        let mut index = self.index.clone();
+        for (address, amm) in changes {
+            // Updates do not change the tokens of an AMM, so the index is only modified when AMMs are added or removed
+            match amm {
+                Some(amm) => {
+                    if !amms.contains_key(address) {
+                        index.insert(amm);
+                    }
+                    amms.insert(*address, Arc::new(amm.clone()));
+                }
+                None => {
+                    if let Some(amm) = amms.remove(address) {
+                        index.remove(&amm);
+                    }
+                }
+            }
+        }
+
+        StateSpaceSnapshot {
+            block_number,
+            amms,
+            index,
+        }
+    }
+}
+
+/// Cheaply clonable handle to the latest published state space snapshot.
+///
+/// Loading a snapshot never blocks the state space from applying new blocks.
+#[derive(Debug, Clone)]
+pub struct StateSpaceSnapshots(Arc<ArcSwap<StateSpaceSnapshot>>);
+
+impl StateSpaceSnapshots {
+    pub fn new(snapshot: StateSpaceSnapshot) -> Self {
+        StateSpaceSnapshots(Arc::new(ArcSwap::from_pointee(snapshot)))
+    }
+
+    /// Returns the latest published snapshot
+    pub fn load(&self) -> Arc<StateSpaceSnapshot> {
+        self.0.load_full()
+    }
+
+    /// Replaces the latest snapshot
+    pub(crate) fn publish(&self, snapshot: StateSpaceSnapshot) {
+        self.0.store(Arc::new(snapshot));
+    }
+
+    /// Publishes a snapshot with the changed AMMs applied to the latest snapshot.
+    /// If `block_number` is `None`, the block number of the latest snapshot is kept.
+    pub(crate) fn publish_changes(
+        &self,
+        block_number: Option<u64>,
+        changes: &[(Address, Option<AMM>)],
+    ) {
+        self.0.rcu(|snapshot| {
+            snapshot.with_changes(block_number.unwrap_or(snapshot.block_number), changes)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::address;
+
+    use super::{StateSpaceSnapshot, StateSpaceSnapshots};
+    use crate::amm::{uniswap_v2::UniswapV2Pool, AutomatedMarketMaker, AMM};
+
+    fn pool_with_reserve(reserve_0: u128) -> AMM {
+        AMM::UniswapV2Pool(UniswapV2Pool {
+            address: address!("B4e16d0168e52d35CaCD2c6185b44281Ec28C9Dc"),
+            reserve_0,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_unchanged_amms_are_shared() {
+        let changed = pool_with_reserve(1);
+        let unchanged = AMM::UniswapV2Pool(UniswapV2Pool {
+            address: address!("0d4a11d5EEaaC28EC3F61d100daF4d40471f1852"),
+            ..Default::default()
+        });
+        let (changed_address, unchanged_address) = (changed.address(), unchanged.address());
+
+        let snapshot = StateSpaceSnapshot::new(1, vec![changed, unchanged]);
+        let next = snapshot.with_changes(2, &[(changed_address, Some(pool_with_reserve(2)))]);
+
+        assert!(std::ptr::eq(
+            snapshot.get(&unchanged_address).unwrap(),
+            next.get(&unchanged_address).unwrap()
+        ));
+        assert!(!std::ptr::eq(
+            snapshot.get(&changed_address).unwrap(),
+            next.get(&changed_address).unwrap()
+        ));
+    }
+
+    #[test]
+    fn test_published_snapshots_are_immutable() {
+        let amm = pool_with_reserve(1);
+        let address = amm.address();
+        let snapshots = StateSpaceSnapshots::new(StateSpaceSnapshot::new(1, vec![amm]));
+
+        let snapshot = snapshots.load();
+        snapshots.publish_changes(Some(2), &[(address, Some(pool_with_reserve(2)))]);
+
+        // Previously loaded snapshots are unaffected by newly published ones
+        let Some(AMM::UniswapV2Pool(pool)) = snapshot.get(&address) else {
+            panic!("Unexpected AMM variant");
+        };
+        assert_eq!((snapshot.block_number(), pool.reserve_0), (1, 1));
+
+        let latest = snapshots.load();
+        let Some(AMM::UniswapV2Pool(pool)) = latest.get(&address) else {
+            panic!("Unexpected AMM variant");
+        };
+        assert_eq!((latest.block_number(), pool.reserve_0), (2, 2));
+        assert_eq!(latest.amms_containing(&pool.token_a), vec![address]);
+
+        snapshots.publish_changes(None, &[(address, None)]);
+
+        let latest = snapshots.load();
+        assert_eq!(latest.block_number(), 2);
+        assert!(latest.is_empty());
+        assert!(latest.amms_containing(&pool.token_a).is_empty());
+    }
+}
@@ -0,0 +1,384 @@
+use std::{fmt::Debug, sync::RwLock};
+
+use alloy::primitives::Address;
+use dashmap::{mapref::entry::Entry, DashMap};
+
+use super::{index::TokenIndex, overlay::same_state, StateSpace};
+use crate::amm::{AutomatedMarketMaker, AMM};
+
+/// Concurrent storage for the AMMs in the state space.
+///
+/// Methods take `&self` so that the store can be shared between the state space manager, running subscriptions and readers.
+/// Implementations must not hold internal locks across calls.
+pub trait StateSpaceStore: Debug + Send + Sync {
+    /// Returns a clone of the AMM at the address
+    fn get(&self, address: &Address) -> Option<AMM>;
+
+    fn contains(&self, address: &Address) -> bool;
+
+    /// Inserts the AMM, returning the AMM previously stored at its address
+    fn insert(&self, amm: AMM) -> Option<AMM>;
+
+    fn remove(&self, address: &Address) -> Option<AMM>;
+
+    /// Replaces the AMM at its address, returning the previous AMM, or `None` without inserting the AMM if it is not in the store
+    fn replace(&self, amm: AMM) -> Option<AMM>;
+
+    /// Applies `f` to the AMM at the address in place, returning `None` if the AMM is not in the store.
+    /// `f` must not change the tokens of the AMM, since they are indexed on insert. Use `replace` to swap in another AMM.
+    fn update<R>(&self, address: &Address, f: impl FnOnce(&mut AMM) -> R) -> Option<R>;
+
+    /// Atomically replaces the AMM at the address with `new` if the store holds the same state as `current`,
+    /// where `None` stands for no AMM at the address. Returns `true` if the AMM was inserted, updated or removed.
+    fn compare_and_swap(&self, address: &Address, current: Option<&AMM>, new: Option<AMM>) -> bool;
+
+    /// Calls `f` with each AMM in the store
+    fn for_each(&self, f: impl FnMut(&AMM));
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn addresses(&self) -> Vec<Address> {
+        let mut addresses = Vec::with_capacity(self.len());
+        self.for_each(|amm| addresses.push(amm.address()));
+        addresses
+    }
+
+    /// Returns a clone of every AMM in the store
+    fn amms(&self) -> Vec<AMM> {
+        let mut amms = Vec::with_capacity(self.len());
+        self.for_each(|amm| amms.push(amm.clone()));
+        amms
+    }
+
+    /// Returns the addresses of the AMMs containing the token
+    fn amms_containing(&self, token: &Address) -> Vec<Address> {
+        let mut addresses = vec![];
+        self.for_each(|amm| {
+            if amm.tokens().contains(token) {
+                addresses.push(amm.address());
+            }
+        });
+        addresses
+    }
+
+    /// Returns the addresses of the AMMs containing both tokens, regardless of order
+    fn amms_for_pair(&self, token_a: &Address, token_b: &Address) -> Vec<Address> {
+        let mut addresses = vec![];
+        self.for_each(|amm| {
+            let tokens = amm.tokens();
+            if tokens.contains(token_a) && tokens.contains(token_b) {
+                addresses.push(amm.address());
+            }
+        });
+        addresses
+    }
+}
+
+/// Stores the state space in a `HashMap` behind a single lock.
+///
+/// This is the default store of the `StateSpaceManager`. Writers block every reader, so state spaces with many concurrent readers
+/// may prefer the `ShardedStateSpace`.
+#[derive(Debug, Default)]
+pub struct LockedStateSpace(RwLock<IndexedStateSpace>);
+
+#[derive(Debug, Default)]
+struct IndexedStateSpace {
+    amms: StateSpace,
+    index: TokenIndex,
+}
+
+impl LockedStateSpace {
+    pub fn new() -> Self {
+        LockedStateSpace::default()
+    }
+}
+
+impl From<Vec<AMM>> for LockedStateSpace {
+    fn from(amms: Vec<AMM>) -> Self {
+        let index = amms.iter().cloned().collect();
+        LockedStateSpace(RwLock::new(IndexedStateSpace {
+            amms: amms.into(),
+            index,
+        }))
+    }
+}
+
+impl StateSpaceStore for LockedStateSpace {
+    fn get(&self, address: &Address) -> Option<AMM> {
+        self.0.read().unwrap().amms.get(address).cloned()
+    }
+
+    fn contains(&self, address: &Address) -> bool {
+        self.0.read().unwrap().amms.contains_key(address)
+    }
+
+    fn insert(&self, amm: AMM) -> Option<AMM> {
+        let mut guard = self.0.write().unwrap();
+        let state = &mut *guard;
+
+        if let Some(prev_amm) = state.amms.get(&amm.address()) {
+            state.index.remove(prev_amm);
+        }
+        state.index.insert(&amm);
+
+        state.amms.insert(amm.address(), amm)
+    }
+
+    fn remove(&self, address: &Address) -> Option<AMM> {
+        let mut state = self.0.write().unwrap();
+
+        let amm = state.amms.remove(address)?;
+        state.index.remove(&amm);
+        Some(amm)
+    }
+
+    fn replace(&self, amm: AMM) -> Option<AMM> {
+        let mut guard = self.0.write().unwrap();
+        let state = &mut *guard;
+
+        let prev_amm = state.amms.get_mut(&amm.address())?;
+        state.index.remove(prev_amm);
+        state.index.insert(&amm);
+
+        Some(std::mem::replace(prev_amm, amm))
+    }
+
+    fn update<R>(&self, address: &Address, f: impl FnOnce(&mut AMM) -> R) -> Option<R> {
+        self.0.write().unwrap().amms.get_mut(address).map(f)
+    }
+
+    fn compare_and_swap(&self, address: &Address, current: Option<&AMM>, new: Option<AMM>) -> bool {
+        let mut guard = self.0.write().unwrap();
+        let state = &mut *guard;
+
+        if !same_state(state.amms.get(address), current) {
+            return false;
+        }
+
+        if let Some(prev_amm) = state.amms.remove(address) {
+            state.index.remove(&prev_amm);
+        }
+        if let Some(amm) = new {
+            state.index.insert(&amm);
+            state.amms.insert(*address, amm);
+        }
+
+        true
+    }
+
+    fn for_each(&self, f: impl FnMut(&AMM)) {
+        self.0.read().unwrap().amms.values().for_each(f);
+    }
+
+    fn len(&self) -> usize {
+        self.0.read().unwrap().amms.len()
+    }
+
+    fn amms_containing(&self, token: &Address) -> Vec<Address> {
+        self.0.read().unwrap().index.amms_containing(token)
+    }
+
+    fn amms_for_pair(&self, token_a: &Address, token_b: &Address) -> Vec<Address> {
+        self.0.read().unwrap().index.amms_for_pair(token_a, token_b)
+    }
+}
+
+/// Stores the state space in a sharded concurrent map.
+///
+/// Opt in by passing it to `StateSpaceManager::with_store`.
+/// Writers only lock the shard containing the AMM, so readers of other AMMs are not blocked while a block is applied.
+/// Readers may observe a block that is partially applied.
+#[derive(Debug, Default)]
+pub struct ShardedStateSpace {
+    amms: DashMap<Address, AMM>,
+    /// Only locked when AMMs are inserted, replaced or removed, since updates do not change the tokens of an AMM
+    index: RwLock<TokenIndex>,
+}
+
+impl ShardedStateSpace {
+    pub fn new() -> Self {
+        ShardedStateSpace::default()
+    }
+}
+
+impl From<Vec<AMM>> for ShardedStateSpace {
+    fn from(amms: Vec<AMM>) -> Self {
+        ShardedStateSpace {
+            index: RwLock::new(amms.iter().cloned().collect()),
+            amms: amms.into_iter().map(|amm| (amm.address(), amm)).collect(),
+        }
+    }
+}
+
+impl StateSpaceStore for ShardedStateSpace {
+    fn get(&self, address: &Address) -> Option<AMM> {
+        self.amms.get(address).map(|amm| amm.clone())
+    }
+
+    fn contains(&self, address: &Address) -> bool {
+        self.amms.contains_key(address)
+    }
+
+    fn insert(&self, amm: AMM) -> Option<AMM> {
+        // Hold the index lock so that the index stays consistent with concurrent inserts and removals
+        let mut index = self.index.write().unwrap();
+
+        if let Some(prev_amm) = self.amms.get(&amm.address()) {
+            index.remove(&prev_amm);
+        }
+        index.insert(&amm);
+
+        self.amms.insert(amm.address(), amm)
+    }
+
+    fn remove(&self, address: &Address) -> Option<AMM> {
+        let mut index = self.index.write().unwrap();
+
+        let (_, amm) = self.amms.remove(address)?;
+        index.remove(&amm);
+        Some(amm)
+    }
+
+    fn replace(&self, amm: AMM) -> Option<AMM> {
+        let mut index = self.index.write().unwrap();
+
+        let mut prev_amm = self.amms.get_mut(&amm.address())?;
+        index.remove(&prev_amm);
+        index.insert(&amm);
+
+        Some(std::mem::replace(&mut *prev_amm, amm))
+    }
+
+    fn update<R>(&self, address: &Address, f: impl FnOnce(&mut AMM) -> R) -> Option<R> {
+        self.amms.get_mut(address).map(|mut amm| f(&mut amm))
+    }
+
+    fn compare_and_swap(&self, address: &Address, current: Option<&AMM>, new: Option<AMM>) -> bool {
+        let mut index = self.index.write().unwrap();
+
+        match self.amms.entry(*address) {
+            Entry::Occupied(mut entry) => {
+                if !same_state(Some(entry.get()), current) {
+                    return false;
+                }
+
+                index.remove(entry.get());
+                match new {
+                    Some(amm) => {
+                        index.insert(&amm);
+                        entry.insert(amm);
+                    }
+                    None => {
+                        entry.remove();
+                    }
+                }
+            }
+            Entry::Vacant(entry) => {
+                if current.is_some() {
+                    return false;
+                }
+
+                if let Some(amm) = new {
+                    index.insert(&amm);
+                    entry.insert(amm);
+                }
+            }
+        }
+
+        true
+    }
+
+    fn for_each(&self, mut f: impl FnMut(&AMM)) {
+        self.amms.iter().for_each(|amm| f(&amm));
+    }
+
+    fn len(&self) -> usize {
+        self.amms.len()
+    }
+
+    fn amms_containing(&self, token: &Address) -> Vec<Address> {
+        self.index.read().unwrap().amms_containing(token)
+    }
+
+    fn amms_for_pair(&self, token_a: &Address, token_b: &Address) -> Vec<Address> {
+        self.index.read().unwrap().amms_for_pair(token_a, token_b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::{address, Address};
+
+    use super::{LockedStateSpace, ShardedStateSpace, StateSpaceStore};
+    use crate::amm::{uniswap_v2::UniswapV2Pool, AutomatedMarketMaker, AMM};
+
+    fn assert_store_behaviour(store: impl StateSpaceStore) {
+        let token_a = Address::with_last_byte(1);
+        let token_b = Address::with_last_byte(2);
+        let amm = AMM::UniswapV2Pool(UniswapV2Pool {
+            address: address!("B4e16d0168e52d35CaCD2c6185b44281Ec28C9Dc"),
+            token_a,
+            token_b,
+            ..Default::default()
+        });
+        let address = amm.address();
+
+        assert!(store.insert(amm).is_none());
+        assert!(store.contains(&address));
+        assert_eq!(store.addresses(), vec![address]);
+        assert_eq!(store.amms_for_pair(&token_b, &token_a), vec![address]);
+        assert_eq!(store.amms_containing(&token_a), vec![address]);
+
+        store.update(&address, |amm| {
+            if let AMM::UniswapV2Pool(pool) = amm {
+                pool.reserve_0 = 1;
+            }
+        });
+
+        let Some(AMM::UniswapV2Pool(pool)) = store.get(&address) else {
+            panic!("Unexpected AMM variant");
+        };
+        assert_eq!(pool.reserve_0, 1);
+
+        // Swaps only apply to the state they were compared against
+        let current = store.get(&address);
+        assert!(!store.compare_and_swap(&address, None, current.clone()));
+        assert!(store.compare_and_swap(&address, current.as_ref(), None));
+        assert!(store.amms_containing(&token_a).is_empty());
+        assert!(!store.compare_and_swap(&address, current.as_ref(), current.clone()));
+        assert!(store.compare_and_swap(&address, None, current.clone()));
+        assert_eq!(store.amms_containing(&token_a), vec![address]);
+
+        // Replacing an AMM reindexes its tokens
+        let token_c = Address::with_last_byte(3);
+        let replaced = store.replace(AMM::UniswapV2Pool(UniswapV2Pool {
+            address,
+            token_a,
+            token_b: token_c,
+            ..Default::default()
+        }));
+        assert!(replaced.is_some());
+        assert!(store.amms_for_pair(&token_a, &token_b).is_empty());
+        assert_eq!(store.amms_for_pair(&token_c, &token_a), vec![address]);
+
+        assert!(store.remove(&address).is_some());
+        assert!(store.update(&address, |_| ()).is_none());
+        assert!(store.replace(replaced.unwrap()).is_none());
+        assert!(store.is_empty());
+        assert!(store.amms_for_pair(&token_a, &token_b).is_empty());
+    }
+
+    #[test]
+    fn test_locked_state_space() {
+        assert_store_behaviour(LockedStateSpace::new());
+    }
+
+    #[test]
+    fn test_sharded_state_space() {
+        assert_store_behaviour(ShardedStateSpace::new());
+    }
+}
@@ -0,0 +1,397 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use alloy::primitives::Address;
+use futures::Stream;
+use tokio::sync::Notify;
+
+use super::event::{AmmStateChange, StateSpaceEvent};
+use crate::amm::AutomatedMarketMaker;
+
+/// How events are delivered to a subscriber that is not keeping up with the state space
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SubscriberPolicy {
+    /// Publishing waits until the subscriber has room for the event, applying backpressure to log processing.
+    /// A single slow subscriber with this policy stalls every other subscriber.
+    #[default]
+    Block,
+    /// The oldest pending event is dropped to make room for the new event, incrementing the lag counter of the subscriber
+    DropOldest,
+    /// Pending changes are merged per AMM address and received as a single `StateSpaceEvent::Coalesced` event,
+    /// keeping the state of each AMM before its first pending change and after its latest change
+    Coalesce,
+}
+
+/// Broadcasts state space events to every subscriber according to the policy of each subscriber.
+///
+/// Subscribers that are dropped are removed without affecting the other subscribers or the publisher.
+#[derive(Debug, Default)]
+pub struct StateSpaceBroadcaster {
+    subscribers: Mutex<Vec<Arc<SubscriberQueue>>>,
+    /// Set once the broadcaster is closed, after which new subscribers are closed immediately
+    closed: AtomicBool,
+    /// Set once a subscription publishes through the broadcaster, so that concurrent subscriptions do not share it
+    claimed: AtomicBool,
+}
+
+impl StateSpaceBroadcaster {
+    pub fn new() -> Self {
+        StateSpaceBroadcaster::default()
+    }
+
+    /// Adds a subscriber that receives every event sent after it subscribed.
+    /// If the broadcaster is closed, the subscriber receives `None`.
+    ///
+    /// `capacity` is the number of pending events buffered for `SubscriberPolicy::Block` and `SubscriberPolicy::DropOldest`,
+    /// and is unused for `SubscriberPolicy::Coalesce`.
+    pub fn subscribe(&self, policy: SubscriberPolicy, capacity: usize) -> StateSpaceSubscriber {
+        assert!(capacity > 0, "Subscriber capacity must be greater than 0");
+
+        let queue = Arc::new(SubscriberQueue::new(policy, capacity));
+
+        // Checked under the subscribers lock so that a concurrent close can not miss the new subscriber
+        let mut subscribers = self.subscribers.lock().unwrap();
+        if self.is_closed() {
+            queue.close();
+        } else {
+            subscribers.push(queue.clone());
+        }
+
+        StateSpaceSubscriber { queue }
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Acquire)
+    }
+
+    /// Claims the broadcaster for a subscription, returning `false` if it was already claimed or is closed
+    pub(crate) fn claim(&self) -> bool {
+        !self.is_closed() && !self.claimed.swap(true, Ordering::AcqRel)
+    }
+
+    /// Returns the number of subscribers that have not been dropped
+    pub fn subscriber_count(&self) -> usize {
+        self.subscribers
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|queue| !queue.is_receiver_dropped())
+            .count()
+    }
+
+    /// Sends the event to every subscriber, waiting for any subscriber with `SubscriberPolicy::Block` that is full
+    pub async fn send(&self, event: StateSpaceEvent) {
+        let subscribers = self.subscribers.lock().unwrap().clone();
+        for queue in subscribers.iter() {
+            queue.push(event.clone()).await;
+        }
+
+        self.subscribers
+            .lock()
+            .unwrap()
+            .retain(|queue| !queue.is_receiver_dropped());
+    }
+
+    /// Returns a guard that closes every subscriber when the task holding it exits, including on error or panic
+    pub(crate) fn close_on_drop(self: &Arc<Self>) -> CloseOnDrop {
+        CloseOnDrop(self.clone())
+    }
+
+    /// Closes the broadcaster and every subscriber, which receive `None` once their pending events are received.
+    ///
+    /// Subscribers added afterwards receive `None` immediately.
+    pub fn close(&self) {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        self.closed.store(true, Ordering::Release);
+
+        for queue in subscribers.drain(..) {
+            queue.close();
+        }
+    }
+}
+
+/// Closes the subscribers of the broadcaster when dropped
+#[derive(Debug)]
+pub(crate) struct CloseOnDrop(Arc<StateSpaceBroadcaster>);
+
+impl Drop for CloseOnDrop {
+    fn drop(&mut self) {
+        self.0.close();
+    }
+}
+
+/// Receives state space events broadcast by a `StateSpaceBroadcaster`
+#[derive(Debug)]
+pub struct StateSpaceSubscriber {
+    queue: Arc<SubscriberQueue>,
+}
+
+impl StateSpaceSubscriber {
+    /// Receives the next event, returning `None` once the subscription is closed and all pending events are received
+    pub async fn recv(&mut self) -> Option<StateSpaceEvent> {
+        loop {
+            {
+                let mut state = self.queue.state.lock().unwrap();
+                if let Some(event) = state.pop() {
+                    drop(state);
+                    self.queue.space_available.notify_one();
+                    return Some(event);
+                }
+
+                if state.closed {
+                    return None;
+                }
+            }
+
+            self.queue.event_available.notified().await;
+        }
+    }
+
+    pub fn policy(&self) -> SubscriberPolicy {
+        self.queue.policy
+    }
+
+    /// Returns the number of events dropped because the subscriber was full, for `SubscriberPolicy::DropOldest`
+    pub fn lagged(&self) -> u64 {
+        self.queue.state.lock().unwrap().lagged
+    }
+
+    /// Converts the subscriber into a stream of events
+    pub fn into_stream(self) -> impl Stream<Item = StateSpaceEvent> {
+        futures::stream::unfold(self, |mut subscriber| async move {
+            subscriber.recv().await.map(|event| (event, subscriber))
+        })
+    }
+}
+
+impl Drop for StateSpaceSubscriber {
+    fn drop(&mut self) {
+        let mut state = self.queue.state.lock().unwrap();
+        state.receiver_dropped = true;
+        state.events.clear();
+        drop(state);
+
+        // Wake the publisher if it is waiting for room
+        self.queue.space_available.notify_one();
+    }
+}
+
+#[derive(Debug)]
+struct SubscriberQueue {
+    policy: SubscriberPolicy,
+    capacity: usize,
+    state: Mutex<SubscriberState>,
+    event_available: Notify,
+    space_available: Notify,
+}
+
+impl SubscriberQueue {
+    fn new(policy: SubscriberPolicy, capacity: usize) -> Self {
+        SubscriberQueue {
+            policy,
+            capacity,
+            state: Mutex::new(SubscriberState::default()),
+            event_available: Notify::new(),
+            space_available: Notify::new(),
+        }
+    }
+
+    async fn push(&self, event: StateSpaceEvent) {
+        loop {
+            {
+                let mut state = self.state.lock().unwrap();
+                if state.receiver_dropped {
+                    return;
+                }
+
+                match self.policy {
+                    SubscriberPolicy::Block => {
+                        if state.events.len() < self.capacity {
+                            state.events.push_back(event);
+                            break;
+                        }
+                    }
+                    SubscriberPolicy::DropOldest => {
+                        if state.events.len() >= self.capacity {
+                            state.events.pop_front();
+                            state.lagged += 1;
+                        }
+                        state.events.push_back(event);
+                        break;
+                    }
+                    SubscriberPolicy::Coalesce => {
+                        state.coalesce(event);
+                        break;
+                    }
+                }
+            }
+
+            self.space_available.notified().await;
+        }
+
+        self.event_available.notify_one();
+    }
+
+    fn close(&self) {
+        self.state.lock().unwrap().closed = true;
+        self.event_available.notify_one();
+    }
+
+    fn is_receiver_dropped(&self) -> bool {
+        self.state.lock().unwrap().receiver_dropped
+    }
+}
+
+#[derive(Debug, Default)]
+struct SubscriberState {
+    events: VecDeque<StateSpaceEvent>,
+    /// Pending changes merged per AMM, in the order each AMM first changed
+    coalesced: Vec<AmmStateChange>,
+    coalesced_index: HashMap<Address, usize>,
+    lagged: u64,
+    closed: bool,
+    receiver_dropped: bool,
+}
+
+impl SubscriberState {
+    fn pop(&mut self) -> Option<StateSpaceEvent> {
+        // Queued removals are delivered before coalesced changes, which never include removed AMMs
+        if let Some(event) = self.events.pop_front() {
+            return Some(event);
+        }
+
+        if !self.coalesced.is_empty() {
+            self.coalesced_index.clear();
+            return Some(StateSpaceEvent::Coalesced(std::mem::take(
+                &mut self.coalesced,
+            )));
+        }
+
+        None
+    }
+
+    fn coalesce(&mut self, event: StateSpaceEvent) {
+        let changes = match event {
+            // Removed AMMs can not be merged into a state change, so pending changes for them are dropped and the removal is queued
+            StateSpaceEvent::AmmsRemoved(amms) => {
+                self.coalesced
+                    .retain(|change| !amms.iter().any(|amm| amm.address() == change.address()));
+                self.coalesced_index = self
+                    .coalesced
+                    .iter()
+                    .enumerate()
+                    .map(|(index, change)| (change.address(), index))
+                    .collect();
+
+                self.events.push_back(StateSpaceEvent::AmmsRemoved(amms));
+                return;
+            }
+            StateSpaceEvent::StateChanged(event) => event.changes,
+            StateSpaceEvent::Unwound { changes, .. }
+            | StateSpaceEvent::Resynced { changes, .. }
+            | StateSpaceEvent::Coalesced(changes) => changes,
+            // Created AMMs have no previous state, so they are recorded as unchanged
+            StateSpaceEvent::AmmsCreated(amms) => amms
+                .into_iter()
+                .map(|amm| AmmStateChange::new(amm.clone(), amm))
+                .collect(),
+        };
+
+        for change in changes {
+            match self.coalesced_index.get(&change.address()) {
+                Some(index) => self.coalesced[*index].new = change.new,
+                None => {
+                    self.coalesced_index
+                        .insert(change.address(), self.coalesced.len());
+                    self.coalesced.push(change);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::address;
+
+    use super::{StateSpaceBroadcaster, SubscriberPolicy};
+    use crate::{
+        amm::{uniswap_v2::UniswapV2Pool, AMM},
+        state_space::event::{AmmStateChange, StateSpaceEvent},
+    };
+
+    fn pool_with_reserve(reserve_0: u128) -> AMM {
+        AMM::UniswapV2Pool(UniswapV2Pool {
+            address: address!("B4e16d0168e52d35CaCD2c6185b44281Ec28C9Dc"),
+            reserve_0,
+            ..Default::default()
+        })
+    }
+
+    fn unwound(block_number: u64, prev: u128, new: u128) -> StateSpaceEvent {
+        StateSpaceEvent::Unwound {
+            block_number,
+            changes: vec![AmmStateChange::new(
+                pool_with_reserve(prev),
+                pool_with_reserve(new),
+            )],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_subscriber_policies() {
+        let broadcaster = StateSpaceBroadcaster::new();
+        let mut drop_oldest = broadcaster.subscribe(SubscriberPolicy::DropOldest, 2);
+        let mut coalesce = broadcaster.subscribe(SubscriberPolicy::Coalesce, 1);
+        let dropped = broadcaster.subscribe(SubscriberPolicy::Block, 1);
+
+        // A dropped subscriber does not stall the publisher
+        drop(dropped);
+
+        for block_number in 1..=3 {
+            broadcaster
+                .send(unwound(
+                    block_number,
+                    block_number as u128,
+                    block_number as u128 + 1,
+                ))
+                .await;
+        }
+        assert_eq!(broadcaster.subscriber_count(), 2);
+
+        // The oldest event was dropped to make room for the newest
+        assert_eq!(drop_oldest.lagged(), 1);
+        let Some(StateSpaceEvent::Unwound { block_number, .. }) = drop_oldest.recv().await else {
+            panic!("Unexpected event");
+        };
+        assert_eq!(block_number, 2);
+
+        // Every change was merged into a single change for the AMM
+        let Some(StateSpaceEvent::Coalesced(changes)) = coalesce.recv().await else {
+            panic!("Unexpected event");
+        };
+        assert_eq!(changes.len(), 1);
+        let (AMM::UniswapV2Pool(prev), AMM::UniswapV2Pool(new)) =
+            (&changes[0].prev, &changes[0].new)
+        else {
+            panic!("Unexpected AMM variant");
+        };
+        assert_eq!((prev.reserve_0, new.reserve_0), (1, 4));
+
+        // Closed subscribers receive their pending events before the subscription ends
+        broadcaster.close();
+        assert!(drop_oldest.recv().await.is_some());
+        assert!(drop_oldest.recv().await.is_none());
+        assert!(coalesce.recv().await.is_none());
+
+        // Subscribers added after the broadcaster is closed are closed immediately
+        let mut late = broadcaster.subscribe(SubscriberPolicy::Block, 1);
+        assert!(late.recv().await.is_none());
+        assert!(!broadcaster.claim());
+    }
+}
@@ -0,0 +1,400 @@
+use std::{collections::HashMap, mem::Discriminant, sync::Arc, time::Duration};
+
+use alloy::{network::Network, primitives::Address, providers::Provider};
+use tokio::{
+    sync::mpsc::{Receiver, Sender},
+    task::JoinHandle,
+    time::MissedTickBehavior,
+};
+
+use super::{
+    error::StateSpaceError, snapshot::StateSpaceSnapshots, store::StateSpaceStore,
+    StateSpaceManager,
+};
+use crate::{
+    amm::{AutomatedMarketMaker, AMM},
+    filters,
+    sync::populate_amms,
+};
+
+/// Default interval between verifications
+pub const DEFAULT_VERIFICATION_INTERVAL: Duration = Duration::from_secs(60);
+/// Default number of AMMs verified per interval
+pub const DEFAULT_VERIFICATION_SAMPLE_SIZE: usize = 16;
+
+/// Configures the background verification of the state space against the chain
+#[derive(Debug, Clone)]
+pub struct VerifierConfig {
+    /// Interval between verifications
+    pub interval: Duration,
+    /// Number of AMMs verified per interval, cycling through every AMM in the state space over successive intervals
+    pub sample_size: usize,
+    /// Replace AMMs that differ from the chain with the state read from the chain
+    pub heal: bool,
+}
+
+impl Default for VerifierConfig {
+    fn default() -> Self {
+        VerifierConfig {
+            interval: DEFAULT_VERIFICATION_INTERVAL,
+            sample_size: DEFAULT_VERIFICATION_SAMPLE_SIZE,
+            heal: false,
+        }
+    }
+}
+
+/// A field that differs between an AMM in the state space and the AMM read from the chain
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldDiff {
+    pub field: &'static str,
+    /// Debug representation of the field in the state space
+    pub local: String,
+    /// Debug representation of the field read from the chain
+    pub chain: String,
+}
+
+/// The fields of an AMM that differ from the chain
+#[derive(Debug, Clone)]
+pub struct AmmDiff {
+    pub address: Address,
+    pub fields: Vec<FieldDiff>,
+    /// Whether the AMM in the state space was replaced with the AMM read from the chain
+    pub healed: bool,
+}
+
+/// The result of verifying a sample of AMMs at a block
+#[derive(Debug, Clone, Default)]
+pub struct VerificationReport {
+    pub block_number: u64,
+    /// Number of AMMs that were read from the chain and compared
+    pub verified: usize,
+    /// AMMs that could not be read from the chain
+    pub failed: Vec<Address>,
+    pub diffs: Vec<AmmDiff>,
+}
+
+impl VerificationReport {
+    pub fn new(block_number: u64) -> Self {
+        VerificationReport {
+            block_number,
+            ..Default::default()
+        }
+    }
+
+    /// Returns true if every verified AMM matches the chain
+    pub fn is_consistent(&self) -> bool {
+        self.diffs.is_empty()
+    }
+}
+
+impl<N, P, S> StateSpaceManager<N, P, S>
+where
+    N: Network,
+    P: Provider<N> + 'static,
+    S: StateSpaceStore + 'static,
+{
+    /// Re-reads the AMMs via the batch request contracts at the block of the latest snapshot and reports any fields that differ.
+    ///
+    /// AMMs are read with a batch request per AMM type. AMMs in a batch that fails are reported as failed.
+    ///
+    /// If `heal` is set, AMMs that differ are replaced with the state read from the chain, as long as no newer block has changed
+    /// the AMM since it was read. Healed state is not recorded in the state change cache, so unwinding a reorg restores the state before healing.
+    pub async fn verify_amms(
+        &self,
+        amm_addresses: &[Address],
+        heal: bool,
+    ) -> Result<VerificationReport, StateSpaceError> {
+        verify_amms(
+            self.state.clone(),
+            self.snapshots.clone(),
+            amm_addresses,
+            heal,
+            self.provider.clone(),
+        )
+        .await
+    }
+
+    /// Spawns a task that verifies a sample of AMMs every interval, sending a report for each verification.
+    ///
+    /// Verification starts once a subscription has published a snapshot at the synced block. See `verify_amms` for how AMMs are healed.
+    pub fn spawn_verifier(
+        &self,
+        config: VerifierConfig,
+        buffer: usize,
+    ) -> (
+        Receiver<VerificationReport>,
+        JoinHandle<Result<(), StateSpaceError>>,
+    ) {
+        let (report_tx, report_rx) = tokio::sync::mpsc::channel(buffer);
+
+        let verifier_handle = tokio::spawn(run_verifier(
+            self.state.clone(),
+            self.snapshots.clone(),
+            config,
+            report_tx,
+            self.provider.clone(),
+        ));
+
+        (report_rx, verifier_handle)
+    }
+}
+
+/// Verifies the next sample of AMMs every interval until the report receiver is dropped
+async fn run_verifier<N, P, S>(
+    state: Arc<S>,
+    snapshots: StateSpaceSnapshots,
+    config: VerifierConfig,
+    report_tx: Sender<VerificationReport>,
+    provider: Arc<P>,
+) -> Result<(), StateSpaceError>
+where
+    N: Network,
+    P: Provider<N>,
+    S: StateSpaceStore,
+{
+    let mut interval = tokio::time::interval(config.interval);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    let mut cursor = 0;
+    loop {
+        interval.tick().await;
+
+        // The snapshot is tagged with block 0 until a subscription has started
+        let snapshot = snapshots.load();
+        if snapshot.block_number() == 0 {
+            continue;
+        }
+
+        let mut amm_addresses = snapshot.amms().map(|amm| amm.address()).collect::<Vec<_>>();
+        amm_addresses.sort();
+
+        let sample = sample_addresses(&amm_addresses, cursor, config.sample_size);
+        cursor = (cursor + sample.len()) % amm_addresses.len().max(1);
+
+        let report = verify_amms(
+            state.clone(),
+            snapshots.clone(),
+            &sample,
+            config.heal,
+            provider.clone(),
+        )
+        .await?;
+
+        report_tx.send(report).await?;
+    }
+}
+
+/// Returns up to `sample_size` addresses starting from `cursor`, wrapping around to the start
+fn sample_addresses(amm_addresses: &[Address], cursor: usize, sample_size: usize) -> Vec<Address> {
+    amm_addresses
+        .iter()
+        .cycle()
+        .skip(cursor)
+        .take(sample_size.min(amm_addresses.len()))
+        .copied()
+        .collect()
+}
+
+async fn verify_amms<N, P, S>(
+    state: Arc<S>,
+    snapshots: StateSpaceSnapshots,
+    amm_addresses: &[Address],
+    heal: bool,
+    provider: Arc<P>,
+) -> Result<VerificationReport, StateSpaceError>
+where
+    N: Network,
+    P: Provider<N>,
+    S: StateSpaceStore,
+{
+    // Compare against the snapshot so that the AMMs are read at the same block their local state reflects
+    let snapshot = snapshots.load();
+    let block_number = snapshot.block_number();
+
+    // Batch requests only accept AMMs of a single type
+    let mut amms_by_type: HashMap<Discriminant<AMM>, Vec<AMM>> = HashMap::new();
+    for address in amm_addresses {
+        if let Some(amm) = snapshot.get(address) {
+            amms_by_type
+                .entry(std::mem::discriminant(amm))
+                .or_default()
+                .push(amm.clone());
+        }
+    }
+
+    let mut report = VerificationReport::new(block_number);
+    for mut chain_amms in amms_by_type.into_values() {
+        if let Err(err) = populate_amms(&mut chain_amms, block_number, provider.clone()).await {
+            tracing::warn!(block_number, ?err, "failed to read amms from chain");
+            report
+                .failed
+                .extend(chain_amms.iter().map(|amm| amm.address()));
+            continue;
+        }
+
+        for chain_amm in chain_amms {
+            let address = chain_amm.address();
+            let Some(local_amm) = snapshot.get(&address) else {
+                continue;
+            };
+
+            // AMMs that could not be populated are emptied by the batch requests
+            if filters::filter_empty_amms(vec![chain_amm.clone()]).is_empty() {
+                report.failed.push(address);
+                continue;
+            }
+
+            report.verified += 1;
+
+            let fields = diff_amms(local_amm, &chain_amm);
+            if fields.is_empty() {
+                continue;
+            }
+
+            tracing::warn!(
+                ?address,
+                block_number,
+                ?fields,
+                "amm state differs from chain"
+            );
+
+            // Compared and replaced atomically by the store, so an AMM changed by a newer block since it was read is never overwritten
+            let healed = heal && state.compare_and_swap(&address, Some(local_amm), Some(chain_amm));
+
+            if healed {
+                snapshots.publish_changes(None, &[(address, state.get(&address))]);
+            }
+
+            report.diffs.push(AmmDiff {
+                address,
+                fields,
+                healed,
+            });
+        }
+    }
+
+    Ok(report)
+}
+
+/// Appends a `FieldDiff` for each listed field that differs between the two AMMs
+macro_rules! diff_fields {
+    ($diffs:ident, $local:ident, $chain:ident, [$($field:ident),+ $(,)?]) => {{
+        $(
+            if $local.$field != $chain.$field {
+                $diffs.push(FieldDiff {
+                    field: stringify!($field),
+                    local: format!("{:?}", $local.$field),
+                    chain: format!("{:?}", $chain.$field),
+                });
+            }
+        )+
+    }};
+}
+
+/// Returns the fields read by the batch request contracts that differ between the two AMMs.
+///
+/// Uniswap V3 ticks are not compared, since they are not read when populating the pool.
+pub fn diff_amms(local: &AMM, chain: &AMM) -> Vec<FieldDiff> {
+    let mut diffs = vec![];
+    match (local, chain) {
+        (AMM::UniswapV2Pool(local), AMM::UniswapV2Pool(chain)) => diff_fields!(
+            diffs,
+            local,
+            chain,
+            [
+                address,
+                token_a,
+                token_a_decimals,
+                token_b,
+                token_b_decimals,
+                reserve_0,
+                reserve_1,
+                fee,
+            ]
+        ),
+        (AMM::UniswapV3Pool(local), AMM::UniswapV3Pool(chain)) => diff_fields!(
+            diffs,
+            local,
+            chain,
+            [
+                address,
+                token_a,
+                token_a_decimals,
+                token_b,
+                token_b_decimals,
+                liquidity,
+                sqrt_price,
+                fee,
+                tick,
+                tick_spacing,
+            ]
+        ),
+        (AMM::ERC4626Vault(local), AMM::ERC4626Vault(chain)) => diff_fields!(
+            diffs,
+            local,
+            chain,
+            [
+                vault_token,
+                vault_token_decimals,
+                asset_token,
+                asset_token_decimals,
+                vault_reserve,
+                asset_reserve,
+                deposit_fee,
+                withdraw_fee,
+            ]
+        ),
+        _ => diffs.push(FieldDiff {
+            field: "variant",
+            local: format!("{:?}", local),
+            chain: format!("{:?}", chain),
+        }),
+    }
+
+    diffs
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::{address, Address};
+
+    use super::{diff_amms, sample_addresses, FieldDiff};
+    use crate::amm::{uniswap_v2::UniswapV2Pool, AMM};
+
+    fn pool_with_reserves(reserve_0: u128, reserve_1: u128) -> AMM {
+        AMM::UniswapV2Pool(UniswapV2Pool {
+            address: address!("B4e16d0168e52d35CaCD2c6185b44281Ec28C9Dc"),
+            reserve_0,
+            reserve_1,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_diff_amms() {
+        let local = pool_with_reserves(1, 2);
+        assert!(diff_amms(&local, &local).is_empty());
+
+        let chain = pool_with_reserves(1, 3);
+        assert_eq!(
+            diff_amms(&local, &chain),
+            vec![FieldDiff {
+                field: "reserve_1",
+                local: "2".to_string(),
+                chain: "3".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_sample_addresses_wraps_around() {
+        let amm_addresses = (0..5).map(Address::with_last_byte).collect::<Vec<_>>();
+
+        assert_eq!(
+            sample_addresses(&amm_addresses, 3, 3),
+            vec![amm_addresses[3], amm_addresses[4], amm_addresses[0]]
+        );
+        assert_eq!(sample_addresses(&amm_addresses, 0, 10).len(), 5);
+        assert!(sample_addresses(&[], 0, 10).is_empty());
+    }
+}
@@ -0,0 +1,168 @@
+use std::{
+    ffi::OsString,
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::errors::CheckpointError;
+
+/// Number of previous checkpoints kept when a full checkpoint is written
+pub const DEFAULT_CHECKPOINT_ROTATIONS: usize = 3;
+
+/// Returns the path of the checkpoint with the suffix appended to its file name
+pub(super) fn with_suffix<P: AsRef<Path>>(checkpoint_path: P, suffix: &str) -> PathBuf {
+    let mut file_name = checkpoint_path
+        .as_ref()
+        .file_name()
+        .map(OsString::from)
+        .unwrap_or_default();
+    file_name.push(suffix);
+
+    checkpoint_path.as_ref().with_file_name(file_name)
+}
+
+/// Returns the path of a previous checkpoint, where rotation 1 is the most recent previous checkpoint
+pub fn rotation_path<P: AsRef<Path>>(checkpoint_path: P, rotation: usize) -> PathBuf {
+    with_suffix(checkpoint_path, &format!(".{rotation}"))
+}
+
+/// Returns the paths of the previous checkpoints, most recent first
+pub fn rotation_files<P: AsRef<Path>>(checkpoint_path: P) -> Vec<PathBuf> {
+    (1..)
+        .map(|rotation| rotation_path(&checkpoint_path, rotation))
+        .take_while(|path| path.exists())
+        .collect()
+}
+
+/// Writes a file so that the path either holds the previous or the new contents if the process crashes.
+///
+/// The contents are written to a temporary file that is synced to disk and then renamed to the path. If `rotations` is
+/// greater than 0, the file currently at the path is kept as rotation 1 and older rotations are shifted up to `rotations`.
+pub fn write_atomic<P, F>(path: P, rotations: usize, write: F) -> Result<(), CheckpointError>
+where
+    P: AsRef<Path>,
+    F: FnOnce(&mut BufWriter<File>) -> Result<(), CheckpointError>,
+{
+    let path = path.as_ref();
+    let temp_path = with_suffix(path, ".tmp");
+
+    let result = write_synced(&temp_path, write);
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+        return result;
+    }
+
+    if rotations > 0 && path.exists() {
+        rotate(path, rotations)?;
+    }
+
+    std::fs::rename(&temp_path, path)?;
+    sync_parent_directory(path)?;
+
+    Ok(())
+}
+
+fn write_synced<F>(path: &Path, write: F) -> Result<(), CheckpointError>
+where
+    F: FnOnce(&mut BufWriter<File>) -> Result<(), CheckpointError>,
+{
+    let mut writer = BufWriter::new(File::create(path)?);
+    write(&mut writer)?;
+    writer.flush()?;
+
+    writer
+        .into_inner()
+        .map_err(|err| err.into_error())?
+        .sync_all()?;
+
+    Ok(())
+}
+
+/// Shifts every rotation up by one, dropping rotations past `rotations`, and moves the checkpoint to rotation 1
+fn rotate(checkpoint_path: &Path, rotations: usize) -> Result<(), CheckpointError> {
+    let oldest = rotation_path(checkpoint_path, rotations);
+    if oldest.exists() {
+        std::fs::remove_file(oldest)?;
+    }
+
+    for rotation in (1..rotations).rev() {
+        let path = rotation_path(checkpoint_path, rotation);
+        if path.exists() {
+            std::fs::rename(path, rotation_path(checkpoint_path, rotation + 1))?;
+        }
+    }
+
+    // If the process crashes before the new checkpoint is renamed into place, loading falls back to this rotation
+    std::fs::rename(checkpoint_path, rotation_path(checkpoint_path, 1))?;
+
+    Ok(())
+}
+
+/// Syncs the directory entry of the renamed file, so that the rename itself survives a crash
+#[cfg(unix)]
+fn sync_parent_directory(path: &Path) -> Result<(), CheckpointError> {
+    let directory = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+
+    File::open(directory)?.sync_all()?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn sync_parent_directory(_path: &Path) -> Result<(), CheckpointError> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::{rotation_files, write_atomic};
+    use crate::{
+        amm::{uniswap_v2::UniswapV2Pool, AMM},
+        errors::CheckpointError,
+        sync::checkpoint::{
+            binary::CheckpointCompression, construct_binary_checkpoint, read_checkpoint,
+        },
+    };
+
+    #[test]
+    fn test_checkpoint_rotation_fallback() {
+        let directory = tempfile::tempdir().unwrap();
+        let checkpoint_path = directory.path().join("checkpoint.bin");
+
+        let amms = vec![AMM::UniswapV2Pool(UniswapV2Pool::default())];
+        for block_number in 100..104 {
+            construct_binary_checkpoint(
+                vec![],
+                &amms,
+                1,
+                block_number,
+                Default::default(),
+                CheckpointCompression::None,
+                &checkpoint_path,
+            )
+            .unwrap();
+        }
+        assert_eq!(rotation_files(&checkpoint_path).len(), 3);
+
+        // A failed write leaves the checkpoint untouched
+        let result = write_atomic(&checkpoint_path, 3, |writer| {
+            writer.write_all(b"partial")?;
+            Err(CheckpointError::UnsupportedVersion(0))
+        });
+        assert!(result.is_err());
+        assert_eq!(read_checkpoint(&checkpoint_path).unwrap().block_number, 103);
+
+        // A truncated checkpoint falls back to the most recent valid rotation
+        let checkpoint = std::fs::read(&checkpoint_path).unwrap();
+        std::fs::write(&checkpoint_path, &checkpoint[..checkpoint.len() / 2]).unwrap();
+        assert_eq!(read_checkpoint(&checkpoint_path).unwrap().block_number, 102);
+
+        std::fs::remove_file(&checkpoint_path).unwrap();
+        assert_eq!(read_checkpoint(&checkpoint_path).unwrap().block_number, 102);
+    }
+}
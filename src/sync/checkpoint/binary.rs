@@ -0,0 +1,358 @@
+use std::io::{BufRead, Read, Write};
+
+use alloy::primitives::B256;
+
+use super::{migration, Checkpoint, CHECKPOINT_VERSION};
+use crate::{
+    amm::{factory::Factory, AMM},
+    errors::CheckpointError,
+};
+
+/// Magic bytes at the start of every binary checkpoint, used to tell binary checkpoints apart from JSON checkpoints
+pub const CHECKPOINT_MAGIC: [u8; 8] = *b"AMMSCKPT";
+/// Compression level used by `CheckpointCompression::default_zstd`
+pub const DEFAULT_ZSTD_LEVEL: i32 = 3;
+
+/// Length in bytes of the header of a binary checkpoint, including the magic bytes
+const HEADER_LEN: usize = 67;
+
+const COMPRESSION_NONE: u8 = 0;
+const COMPRESSION_ZSTD: u8 = 1;
+
+/// Compression applied to the payload of a binary checkpoint
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CheckpointCompression {
+    #[default]
+    None,
+    /// The level is only used when writing, so headers read from a checkpoint always report level 0
+    Zstd { level: i32 },
+}
+
+impl CheckpointCompression {
+    pub fn default_zstd() -> Self {
+        CheckpointCompression::Zstd {
+            level: DEFAULT_ZSTD_LEVEL,
+        }
+    }
+}
+
+/// The header of a binary checkpoint, which can be read without decoding the AMMs.
+///
+/// Binary checkpoints are laid out as follows, with integers encoded little endian:
+///
+/// | Offset | Size | Field                                                            |
+/// |--------|------|------------------------------------------------------------------|
+/// | 0      | 8    | Magic bytes `AMMSCKPT`                                           |
+/// | 8      | 2    | Checkpoint schema version                                        |
+/// | 10     | 1    | Compression, 0 for none and 1 for zstd                           |
+/// | 11     | 8    | Chain id                                                         |
+/// | 19     | 8    | Block number                                                     |
+/// | 27     | 32   | Block hash                                                       |
+/// | 59     | 8    | Timestamp                                                        |
+/// | 67     | ..   | Bincode encoded factories and AMMs, compressed as specified      |
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CheckpointHeader {
+    pub version: u16,
+    pub compression: CheckpointCompression,
+    pub chain_id: u64,
+    pub block_number: u64,
+    pub block_hash: B256,
+    pub timestamp: u64,
+}
+
+impl CheckpointHeader {
+    pub fn new(
+        chain_id: u64,
+        block_number: u64,
+        block_hash: B256,
+        timestamp: u64,
+        compression: CheckpointCompression,
+    ) -> Self {
+        CheckpointHeader {
+            version: CHECKPOINT_VERSION,
+            compression,
+            chain_id,
+            block_number,
+            block_hash,
+            timestamp,
+        }
+    }
+
+    fn to_bytes(self) -> [u8; HEADER_LEN] {
+        let compression = match self.compression {
+            CheckpointCompression::None => COMPRESSION_NONE,
+            CheckpointCompression::Zstd { .. } => COMPRESSION_ZSTD,
+        };
+
+        let mut bytes = [0; HEADER_LEN];
+        bytes[0..8].copy_from_slice(&CHECKPOINT_MAGIC);
+        bytes[8..10].copy_from_slice(&self.version.to_le_bytes());
+        bytes[10] = compression;
+        bytes[11..19].copy_from_slice(&self.chain_id.to_le_bytes());
+        bytes[19..27].copy_from_slice(&self.block_number.to_le_bytes());
+        bytes[27..59].copy_from_slice(self.block_hash.as_slice());
+        bytes[59..67].copy_from_slice(&self.timestamp.to_le_bytes());
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8; HEADER_LEN]) -> Result<Self, CheckpointError> {
+        let version = u16::from_le_bytes([bytes[8], bytes[9]]);
+        if !migration::is_supported_binary_version(version) {
+            return Err(CheckpointError::UnsupportedVersion(version));
+        }
+
+        let compression = match bytes[10] {
+            COMPRESSION_NONE => CheckpointCompression::None,
+            COMPRESSION_ZSTD => CheckpointCompression::Zstd { level: 0 },
+            compression => return Err(CheckpointError::UnknownCompression(compression)),
+        };
+
+        Ok(CheckpointHeader {
+            version,
+            compression,
+            chain_id: read_u64(&bytes[11..19]),
+            block_number: read_u64(&bytes[19..27]),
+            block_hash: B256::from_slice(&bytes[27..59]),
+            timestamp: read_u64(&bytes[59..67]),
+        })
+    }
+}
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    u64::from_le_bytes(bytes.try_into().expect("Slice should be 8 bytes"))
+}
+
+/// Returns true if the reader is positioned at the start of a binary checkpoint, without consuming any bytes
+pub fn is_binary_checkpoint<R: BufRead>(reader: &mut R) -> Result<bool, CheckpointError> {
+    Ok(reader.fill_buf()?.starts_with(&CHECKPOINT_MAGIC))
+}
+
+/// Writes a binary checkpoint with the header and payload
+pub fn write_binary_checkpoint<W: Write>(
+    mut writer: W,
+    header: &CheckpointHeader,
+    factories: &[Factory],
+    amms: &[AMM],
+) -> Result<(), CheckpointError> {
+    writer.write_all(&header.to_bytes())?;
+
+    match header.compression {
+        CheckpointCompression::None => bincode::serialize_into(&mut writer, &(factories, amms))?,
+        CheckpointCompression::Zstd { level } => {
+            let mut encoder = zstd::stream::write::Encoder::new(&mut writer, level)?;
+            bincode::serialize_into(&mut encoder, &(factories, amms))?;
+            encoder.finish()?;
+        }
+    }
+
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Reads the header of a binary checkpoint
+pub fn read_binary_checkpoint_header<R: Read>(
+    mut reader: R,
+) -> Result<CheckpointHeader, CheckpointError> {
+    let mut bytes = [0; HEADER_LEN];
+    reader.read_exact(&mut bytes)?;
+
+    if bytes[0..8] != CHECKPOINT_MAGIC {
+        return Err(CheckpointError::IOError(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "missing binary checkpoint magic bytes",
+        )));
+    }
+
+    CheckpointHeader::from_bytes(&bytes)
+}
+
+/// Reads a binary checkpoint, returning its header and contents migrated to the current schema version
+pub fn read_binary_checkpoint<R: Read>(
+    mut reader: R,
+) -> Result<(CheckpointHeader, Checkpoint), CheckpointError> {
+    let header = read_binary_checkpoint_header(&mut reader)?;
+
+    let (factories, amms) = match header.compression {
+        CheckpointCompression::None => migration::migrate_binary_payload(header.version, reader)?,
+        CheckpointCompression::Zstd { .. } => migration::migrate_binary_payload(
+            header.version,
+            zstd::stream::read::Decoder::new(reader)?,
+        )?,
+    };
+
+    let checkpoint = Checkpoint::new(
+        header.timestamp as usize,
+        header.block_number,
+        factories,
+        amms,
+    );
+
+    Ok((header, checkpoint))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use alloy::{
+        primitives::{address, B256, U64},
+        providers::ProviderBuilder,
+        transports::mock::Asserter,
+    };
+
+    use super::{
+        read_binary_checkpoint, write_binary_checkpoint, CheckpointCompression, CheckpointHeader,
+    };
+    use crate::{
+        amm::{
+            factory::Factory,
+            uniswap_v2::{factory::UniswapV2Factory, UniswapV2Pool},
+            uniswap_v3::UniswapV3Pool,
+            AMM,
+        },
+        errors::{AMMError, CheckpointError},
+        sync::checkpoint::{
+            construct_binary_checkpoint, construct_checkpoint, deconstruct_checkpoint,
+            read_checkpoint_header, sync_amms_from_checkpoint,
+        },
+    };
+
+    #[test]
+    fn test_binary_checkpoint_round_trip() {
+        let factories = vec![Factory::UniswapV2Factory(UniswapV2Factory::new(
+            address!("5C69bEe701ef814a2B6a3EDD4B1652CB9cc5aA6f"),
+            2638438,
+            300,
+        ))];
+
+        let mut v3_pool = UniswapV3Pool {
+            address: address!("88e6A0c2dDD26FEEb64F039a2c41296FcB3f5640"),
+            liquidity: 1,
+            ..Default::default()
+        };
+        v3_pool.tick_bitmap.insert(-1, alloy::primitives::U256::MAX);
+
+        let amms = vec![
+            AMM::UniswapV2Pool(UniswapV2Pool {
+                address: address!("B4e16d0168e52d35CaCD2c6185b44281Ec28C9Dc"),
+                reserve_0: u128::MAX,
+                ..Default::default()
+            }),
+            AMM::UniswapV3Pool(v3_pool),
+        ];
+
+        for compression in [
+            CheckpointCompression::None,
+            CheckpointCompression::default_zstd(),
+        ] {
+            let header =
+                CheckpointHeader::new(1, 19_000_000, B256::with_last_byte(1), 1, compression);
+
+            let mut bytes = vec![];
+            write_binary_checkpoint(&mut bytes, &header, &factories, &amms).unwrap();
+
+            let (read_header, checkpoint) = read_binary_checkpoint(bytes.as_slice()).unwrap();
+            assert_eq!(read_header.block_hash, header.block_hash);
+            assert_eq!(read_header.chain_id, 1);
+            assert_eq!(checkpoint.block_number, 19_000_000);
+            assert_eq!(checkpoint.factories.len(), 1);
+
+            let AMM::UniswapV2Pool(pool) = &checkpoint.amms[0] else {
+                panic!("Unexpected AMM variant");
+            };
+            assert_eq!(pool.reserve_0, u128::MAX);
+
+            let AMM::UniswapV3Pool(pool) = &checkpoint.amms[1] else {
+                panic!("Unexpected AMM variant");
+            };
+            assert_eq!(
+                pool.tick_bitmap.get(&-1),
+                Some(&alloy::primitives::U256::MAX)
+            );
+        }
+    }
+
+    #[test]
+    fn test_checkpoint_format_detection() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let directory = temp_dir.path();
+
+        let amms = vec![AMM::UniswapV2Pool(UniswapV2Pool {
+            address: address!("B4e16d0168e52d35CaCD2c6185b44281Ec28C9Dc"),
+            reserve_0: 1,
+            ..Default::default()
+        })];
+
+        let json_path = directory.join("checkpoint.json");
+        construct_checkpoint(vec![], &amms, 100, &json_path).unwrap();
+
+        let binary_path = directory.join("checkpoint.bin");
+        construct_binary_checkpoint(
+            vec![],
+            &amms,
+            1,
+            100,
+            B256::ZERO,
+            CheckpointCompression::default_zstd(),
+            &binary_path,
+        )
+        .unwrap();
+
+        assert!(read_checkpoint_header(&json_path).unwrap().is_none());
+        assert_eq!(
+            read_checkpoint_header(&binary_path)
+                .unwrap()
+                .unwrap()
+                .compression,
+            CheckpointCompression::Zstd { level: 0 }
+        );
+
+        for path in [json_path, binary_path] {
+            let (amms, block_number) = deconstruct_checkpoint(path).unwrap();
+            assert_eq!(amms.len(), 1);
+            assert_eq!(block_number, 100);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_non_canonical_checkpoint_with_ticks() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("checkpoint.bin");
+
+        let amms = vec![AMM::UniswapV3Pool(UniswapV3Pool {
+            address: address!("88e6A0c2dDD26FEEb64F039a2c41296FcB3f5640"),
+            liquidity: 1,
+            ..Default::default()
+        })];
+        construct_binary_checkpoint(
+            vec![],
+            &amms,
+            1,
+            100,
+            B256::with_last_byte(1),
+            CheckpointCompression::None,
+            &path,
+        )
+        .unwrap();
+
+        // The provider is at block 110 on the same chain, and no longer has the checkpoint block
+        let asserter = Asserter::new();
+        asserter.push_success(&U64::from(110));
+        asserter.push_success(&U64::from(1));
+        asserter.push_success(&None::<()>);
+
+        let provider = Arc::new(
+            ProviderBuilder::new()
+                .disable_recommended_fillers()
+                .connect_mocked_client(asserter),
+        );
+
+        assert!(matches!(
+            sync_amms_from_checkpoint(&path, 100, provider).await,
+            Err(AMMError::CheckpointError(
+                CheckpointError::NonCanonicalTicks(100)
+            ))
+        ));
+    }
+}
@@ -0,0 +1,429 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+};
+
+use alloy::primitives::Address;
+use serde::{Deserialize, Serialize};
+
+use super::{atomic, Checkpoint, CHECKPOINT_VERSION};
+use crate::{
+    amm::{factory::Factory, AutomatedMarketMaker, AMM},
+    errors::CheckpointError,
+    state_space::verifier::diff_amms,
+};
+
+/// Number of deltas after which `construct_checkpoint_delta` compacts the deltas into the base checkpoint
+pub const DEFAULT_MAX_CHECKPOINT_DELTAS: usize = 32;
+
+/// Separates the checkpoint file name from the block number of a delta, i.e. `checkpoint.json.delta-00000000000018500000`
+const DELTA_SEPARATOR: &str = ".delta-";
+
+/// The AMMs added, modified or removed between two checkpoint blocks
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CheckpointDelta {
+    pub version: u16,
+    pub timestamp: usize,
+    /// Block number of the checkpoint the delta applies to
+    pub from_block: u64,
+    pub block_number: u64,
+    pub factories: Vec<Factory>,
+    /// AMMs that were added or modified since `from_block`
+    pub amms: Vec<AMM>,
+    /// AMMs that were removed since `from_block`
+    pub removed: Vec<Address>,
+}
+
+impl CheckpointDelta {
+    /// Returns the delta from the checkpoint to the AMMs at `block_number`
+    pub fn between(
+        checkpoint: &Checkpoint,
+        timestamp: usize,
+        block_number: u64,
+        factories: Vec<Factory>,
+        amms: &[AMM],
+    ) -> CheckpointDelta {
+        let prev_amms = checkpoint
+            .amms
+            .iter()
+            .map(|amm| (amm.address(), amm))
+            .collect::<HashMap<_, _>>();
+
+        let changed_amms = amms
+            .iter()
+            .filter(|amm| {
+                prev_amms
+                    .get(&amm.address())
+                    .is_none_or(|prev_amm| amm_changed(prev_amm, amm))
+            })
+            .cloned()
+            .collect();
+
+        let amm_addresses = amms.iter().map(|amm| amm.address()).collect::<HashSet<_>>();
+
+        let removed = checkpoint
+            .amms
+            .iter()
+            .map(|amm| amm.address())
+            .filter(|address| !amm_addresses.contains(address))
+            .collect();
+
+        CheckpointDelta {
+            version: CHECKPOINT_VERSION,
+            timestamp,
+            from_block: checkpoint.block_number,
+            block_number,
+            factories,
+            amms: changed_amms,
+            removed,
+        }
+    }
+
+    /// Applies the delta to the checkpoint, which must be at the block the delta was taken from
+    pub fn apply(self, checkpoint: &mut Checkpoint) -> Result<(), CheckpointError> {
+        if self.from_block != checkpoint.block_number {
+            return Err(CheckpointError::DeltaGap(
+                self.from_block,
+                checkpoint.block_number,
+            ));
+        }
+
+        let removed = self.removed.into_iter().collect::<HashSet<_>>();
+        let mut changed_amms = self
+            .amms
+            .into_iter()
+            .map(|amm| (amm.address(), amm))
+            .collect::<HashMap<_, _>>();
+
+        // Keep the order of the existing AMMs and append the AMMs added by the delta
+        let mut amms = std::mem::take(&mut checkpoint.amms)
+            .into_iter()
+            .filter(|amm| !removed.contains(&amm.address()))
+            .map(|amm| changed_amms.remove(&amm.address()).unwrap_or(amm))
+            .collect::<Vec<_>>();
+        amms.extend(changed_amms.into_values());
+
+        checkpoint.amms = amms;
+        checkpoint.factories = self.factories;
+        checkpoint.timestamp = self.timestamp;
+        checkpoint.block_number = self.block_number;
+
+        Ok(())
+    }
+}
+
+/// Returns true if any persisted state differs between the two AMMs
+fn amm_changed(prev: &AMM, new: &AMM) -> bool {
+    if !diff_amms(prev, new).is_empty() {
+        return true;
+    }
+
+    // Ticks are not compared by `diff_amms`
+    match (prev, new) {
+        (AMM::UniswapV3Pool(prev), AMM::UniswapV3Pool(new)) => {
+            prev.tick_bitmap != new.tick_bitmap
+                || prev.ticks.len() != new.ticks.len()
+                || prev.ticks.iter().any(|(tick, info)| {
+                    new.ticks.get(tick).is_none_or(|new_info| {
+                        info.liquidity_gross != new_info.liquidity_gross
+                            || info.liquidity_net != new_info.liquidity_net
+                            || info.initialized != new_info.initialized
+                    })
+                })
+        }
+        _ => false,
+    }
+}
+
+/// Returns the path of the delta to the checkpoint at the block number
+pub fn delta_path<P: AsRef<Path>>(checkpoint_path: P, block_number: u64) -> PathBuf {
+    atomic::with_suffix(
+        checkpoint_path,
+        &format!("{DELTA_SEPARATOR}{block_number:020}"),
+    )
+}
+
+/// Returns the block number and path of every delta to the checkpoint, in block order
+pub fn delta_files<P: AsRef<Path>>(
+    checkpoint_path: P,
+) -> Result<Vec<(u64, PathBuf)>, CheckpointError> {
+    let checkpoint_path = checkpoint_path.as_ref();
+    let Some(checkpoint_file_name) = checkpoint_path.file_name().and_then(|name| name.to_str())
+    else {
+        return Ok(vec![]);
+    };
+
+    let directory = match checkpoint_path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+
+    if !directory.exists() {
+        return Ok(vec![]);
+    }
+
+    let prefix = format!("{checkpoint_file_name}{DELTA_SEPARATOR}");
+    let mut deltas = vec![];
+    for entry in std::fs::read_dir(directory)? {
+        let entry = entry?;
+        let file_name = entry.file_name();
+        let block_number = file_name
+            .to_str()
+            .and_then(|name| name.strip_prefix(&prefix))
+            .and_then(|block_number| block_number.parse::<u64>().ok());
+
+        if let Some(block_number) = block_number {
+            deltas.push((block_number, checkpoint_path.with_file_name(file_name)));
+        }
+    }
+
+    deltas.sort();
+    Ok(deltas)
+}
+
+/// Atomically writes the delta next to the checkpoint
+pub fn write_delta<P: AsRef<Path>>(
+    checkpoint_path: P,
+    delta: &CheckpointDelta,
+) -> Result<(), CheckpointError> {
+    atomic::write_atomic(
+        delta_path(checkpoint_path, delta.block_number),
+        0,
+        |writer| Ok(serde_json::to_writer(writer, delta)?),
+    )
+}
+
+/// Reads a delta written by `write_delta`
+pub fn read_delta<P: AsRef<Path>>(delta_path: P) -> Result<CheckpointDelta, CheckpointError> {
+    let delta: CheckpointDelta = serde_json::from_reader(BufReader::new(File::open(delta_path)?))?;
+
+    if delta.version != CHECKPOINT_VERSION {
+        return Err(CheckpointError::UnsupportedVersion(delta.version));
+    }
+
+    Ok(delta)
+}
+
+/// Applies every delta after the checkpoint block in block order, ignoring deltas at or before the checkpoint block
+pub fn apply_deltas<P: AsRef<Path>>(
+    checkpoint_path: P,
+    checkpoint: &mut Checkpoint,
+) -> Result<usize, CheckpointError> {
+    let mut applied = 0;
+    for (block_number, path) in delta_files(checkpoint_path)? {
+        if block_number <= checkpoint.block_number {
+            continue;
+        }
+
+        read_delta(path)?.apply(checkpoint)?;
+        applied += 1;
+    }
+
+    Ok(applied)
+}
+
+/// Applies deltas after the checkpoint block in block order until a delta cannot be read or applied, leaving the
+/// checkpoint at the block of the latest valid delta
+pub fn apply_valid_deltas<P: AsRef<Path>>(
+    checkpoint_path: P,
+    checkpoint: &mut Checkpoint,
+) -> usize {
+    let delta_files = match delta_files(checkpoint_path) {
+        Ok(delta_files) => delta_files,
+        Err(err) => {
+            tracing::warn!(?err, "failed to list checkpoint deltas");
+            return 0;
+        }
+    };
+
+    let mut applied = 0;
+    for (block_number, path) in delta_files {
+        if block_number <= checkpoint.block_number {
+            continue;
+        }
+
+        if let Err(err) = read_delta(&path).and_then(|delta| delta.apply(checkpoint)) {
+            tracing::warn!(
+                ?path,
+                ?err,
+                block_number = checkpoint.block_number,
+                "failed to apply checkpoint delta, skipping later deltas"
+            );
+            break;
+        }
+
+        applied += 1;
+    }
+
+    applied
+}
+
+/// Removes every delta to the checkpoint
+pub fn remove_deltas<P: AsRef<Path>>(checkpoint_path: P) -> Result<(), CheckpointError> {
+    for (_, path) in delta_files(checkpoint_path)? {
+        std::fs::remove_file(path)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::Address;
+
+    use super::CheckpointDelta;
+    use crate::{
+        amm::{uniswap_v2::UniswapV2Pool, AutomatedMarketMaker, AMM},
+        sync::checkpoint::{
+            compact_checkpoint, construct_checkpoint, construct_checkpoint_delta,
+            delta::{delta_files, delta_path},
+            read_checkpoint, read_checkpoint_with_deltas, Checkpoint,
+        },
+    };
+
+    fn pool(address: u8, reserve_0: u128) -> AMM {
+        AMM::UniswapV2Pool(UniswapV2Pool {
+            address: Address::with_last_byte(address),
+            reserve_0,
+            ..Default::default()
+        })
+    }
+
+    fn reserve_0(amm: &AMM) -> u128 {
+        match amm {
+            AMM::UniswapV2Pool(pool) => pool.reserve_0,
+            _ => panic!("Unexpected AMM variant"),
+        }
+    }
+
+    #[test]
+    fn test_checkpoint_delta() {
+        let checkpoint = Checkpoint::new(0, 100, vec![], vec![pool(1, 1), pool(2, 2), pool(3, 3)]);
+
+        let amms = vec![pool(1, 1), pool(2, 20), pool(4, 4)];
+        let delta = CheckpointDelta::between(&checkpoint, 1, 101, vec![], &amms);
+        assert_eq!(
+            delta
+                .amms
+                .iter()
+                .map(|amm| amm.address())
+                .collect::<Vec<_>>(),
+            vec![Address::with_last_byte(2), Address::with_last_byte(4)]
+        );
+        assert_eq!(delta.removed, vec![Address::with_last_byte(3)]);
+
+        let mut applied = checkpoint.clone();
+        delta.clone().apply(&mut applied).unwrap();
+        assert_eq!(applied.block_number, 101);
+        assert_eq!(
+            applied.amms.iter().map(reserve_0).collect::<Vec<_>>(),
+            vec![1, 20, 4]
+        );
+
+        // Deltas only apply to the block they were taken from
+        assert!(delta.apply(&mut applied).is_err());
+    }
+
+    #[test]
+    fn test_incremental_checkpoint() {
+        let directory = tempfile::tempdir().unwrap();
+        let checkpoint_path = directory.path().join("checkpoint.json");
+
+        construct_checkpoint(vec![], &[pool(1, 1), pool(2, 2)], 100, &checkpoint_path).unwrap();
+
+        // The checkpoint is only read once and advanced by each delta
+        let mut checkpoint = read_checkpoint(&checkpoint_path).unwrap();
+        construct_checkpoint_delta(
+            &mut checkpoint,
+            vec![],
+            &[pool(1, 10), pool(2, 2)],
+            101,
+            &checkpoint_path,
+        )
+        .unwrap();
+        construct_checkpoint_delta(
+            &mut checkpoint,
+            vec![],
+            &[pool(1, 10), pool(3, 3)],
+            102,
+            &checkpoint_path,
+        )
+        .unwrap();
+        assert_eq!(delta_files(&checkpoint_path).unwrap().len(), 2);
+        assert_eq!(checkpoint.block_number, 102);
+
+        let read = read_checkpoint(&checkpoint_path).unwrap();
+        assert_eq!(read.block_number, 102);
+        assert_eq!(
+            read.amms.iter().map(reserve_0).collect::<Vec<_>>(),
+            vec![10, 3]
+        );
+        assert_eq!(
+            checkpoint.amms.iter().map(reserve_0).collect::<Vec<_>>(),
+            vec![10, 3]
+        );
+
+        compact_checkpoint(&checkpoint_path).unwrap();
+        assert!(delta_files(&checkpoint_path).unwrap().is_empty());
+
+        let compacted = read_checkpoint(&checkpoint_path).unwrap();
+        assert_eq!(compacted.block_number, 102);
+        assert_eq!(
+            compacted.amms.iter().map(reserve_0).collect::<Vec<_>>(),
+            vec![10, 3]
+        );
+
+        // Full checkpoints remove the deltas to the previous checkpoint
+        construct_checkpoint_delta(
+            &mut checkpoint,
+            vec![],
+            &[pool(1, 11)],
+            103,
+            &checkpoint_path,
+        )
+        .unwrap();
+        construct_checkpoint(vec![], &[pool(1, 12)], 104, &checkpoint_path).unwrap();
+        assert!(delta_files(&checkpoint_path).unwrap().is_empty());
+        assert_eq!(read_checkpoint(&checkpoint_path).unwrap().block_number, 104);
+    }
+
+    #[test]
+    fn test_checkpoint_with_deltas_is_delta_base() {
+        let directory = tempfile::tempdir().unwrap();
+        let checkpoint_path = directory.path().join("checkpoint.json");
+
+        construct_checkpoint(vec![], &[pool(1, 1)], 100, &checkpoint_path).unwrap();
+        let (_, mut checkpoint, is_delta_base) =
+            read_checkpoint_with_deltas(&checkpoint_path).unwrap();
+        assert!(is_delta_base);
+
+        construct_checkpoint_delta(
+            &mut checkpoint,
+            vec![],
+            &[pool(1, 2)],
+            101,
+            &checkpoint_path,
+        )
+        .unwrap();
+        construct_checkpoint_delta(
+            &mut checkpoint,
+            vec![],
+            &[pool(1, 3)],
+            102,
+            &checkpoint_path,
+        )
+        .unwrap();
+
+        let (_, checkpoint, is_delta_base) = read_checkpoint_with_deltas(&checkpoint_path).unwrap();
+        assert!(is_delta_base);
+        assert_eq!(checkpoint.block_number, 102);
+
+        // A new delta would not be applied after a delta that can not be read
+        std::fs::write(delta_path(&checkpoint_path, 101), b"corrupt").unwrap();
+        let (_, checkpoint, is_delta_base) = read_checkpoint_with_deltas(&checkpoint_path).unwrap();
+        assert!(!is_delta_base);
+        assert_eq!(checkpoint.block_number, 100);
+    }
+}
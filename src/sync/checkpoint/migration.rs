@@ -0,0 +1,137 @@
+//! Upgrades checkpoints written with an older schema to the current `Checkpoint`.
+//!
+//! Schema history:
+//! - Version 0: JSON checkpoints without a `version` field
+//! - Version 1: Adds the `version` field, and is the first version written in the binary format and with deltas
+//!
+//! When the layout of `Checkpoint` or a serialized AMM changes, bump `CHECKPOINT_VERSION`, freeze the previous layout
+//! as a `CheckpointV{n}` struct in this module and add a migration from it to the next version.
+
+use std::io::Read;
+
+use serde::Deserialize;
+
+use super::{Checkpoint, CHECKPOINT_VERSION};
+use crate::{
+    amm::{factory::Factory, AMM},
+    errors::CheckpointError,
+};
+
+/// The first schema version written in the binary format
+pub const FIRST_BINARY_CHECKPOINT_VERSION: u16 = 1;
+
+/// Only reads the version of a JSON checkpoint, skipping the remaining fields
+#[derive(Deserialize)]
+struct CheckpointVersion {
+    /// Checkpoints written before the schema was versioned have no version field
+    #[serde(default)]
+    version: u16,
+}
+
+/// A checkpoint written before the schema was versioned
+#[derive(Deserialize)]
+pub struct CheckpointV0 {
+    pub timestamp: usize,
+    pub block_number: u64,
+    pub factories: Vec<Factory>,
+    pub amms: Vec<AMM>,
+}
+
+impl From<CheckpointV0> for Checkpoint {
+    fn from(checkpoint: CheckpointV0) -> Self {
+        Checkpoint::new(
+            checkpoint.timestamp,
+            checkpoint.block_number,
+            checkpoint.factories,
+            checkpoint.amms,
+        )
+    }
+}
+
+/// Deserializes a JSON checkpoint of any supported version, migrating it to the current version
+pub fn migrate_json_checkpoint(checkpoint: &str) -> Result<Checkpoint, CheckpointError> {
+    let CheckpointVersion { version } = serde_json::from_str(checkpoint)?;
+
+    match version {
+        0 => Ok(serde_json::from_str::<CheckpointV0>(checkpoint)?.into()),
+        CHECKPOINT_VERSION => Ok(serde_json::from_str(checkpoint)?),
+        version => Err(CheckpointError::UnsupportedVersion(version)),
+    }
+}
+
+/// Returns true if binary checkpoints of the version can be read
+pub fn is_supported_binary_version(version: u16) -> bool {
+    (FIRST_BINARY_CHECKPOINT_VERSION..=CHECKPOINT_VERSION).contains(&version)
+}
+
+/// Deserializes the factories and AMMs of a binary checkpoint payload of the version, migrating them to the current version
+pub fn migrate_binary_payload<R: Read>(
+    version: u16,
+    reader: R,
+) -> Result<(Vec<Factory>, Vec<AMM>), CheckpointError> {
+    match version {
+        CHECKPOINT_VERSION => Ok(bincode::deserialize_from(reader)?),
+        version => Err(CheckpointError::UnsupportedVersion(version)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::{address, U256};
+
+    use super::migrate_json_checkpoint;
+    use crate::{
+        amm::{factory::Factory, AMM},
+        errors::CheckpointError,
+        sync::checkpoint::{Checkpoint, CHECKPOINT_VERSION},
+    };
+
+    const CHECKPOINT_V0: &str = include_str!("fixtures/checkpoint_v0.json");
+    const CHECKPOINT_V1: &str = include_str!("fixtures/checkpoint_v1.json");
+
+    fn assert_fixture(checkpoint: &Checkpoint) {
+        assert_eq!(checkpoint.version, CHECKPOINT_VERSION);
+        assert_eq!(checkpoint.timestamp, 1700000000);
+        assert_eq!(checkpoint.block_number, 18_500_000);
+
+        assert!(matches!(
+            checkpoint.factories.as_slice(),
+            [Factory::UniswapV2Factory(_), Factory::UniswapV3Factory(_)]
+        ));
+
+        let [AMM::UniswapV2Pool(v2_pool), AMM::UniswapV3Pool(v3_pool), AMM::ERC4626Vault(vault)] =
+            checkpoint.amms.as_slice()
+        else {
+            panic!("Unexpected AMMs");
+        };
+
+        assert_eq!(
+            v2_pool.address,
+            address!("B4e16d0168e52d35CaCD2c6185b44281Ec28C9Dc")
+        );
+        // Reserves above u64::MAX must not lose precision
+        assert_eq!(v2_pool.reserve_0, 340282366920938463463374607431768211455);
+        assert_eq!(v3_pool.tick_bitmap.get(&-58), Some(&U256::from(1)));
+        assert_eq!(v3_pool.ticks.get(&-887220).unwrap().liquidity_net, 100);
+        assert_eq!(vault.asset_reserve, U256::from(1000000));
+    }
+
+    #[test]
+    fn test_migrate_checkpoint_v0() {
+        assert_fixture(&migrate_json_checkpoint(CHECKPOINT_V0).unwrap());
+    }
+
+    #[test]
+    fn test_migrate_checkpoint_v1() {
+        assert_fixture(&migrate_json_checkpoint(CHECKPOINT_V1).unwrap());
+    }
+
+    #[test]
+    fn test_migrate_checkpoint_unsupported_version() {
+        let checkpoint = CHECKPOINT_V1.replacen("\"version\": 1", "\"version\": 999", 1);
+        assert!(matches!(
+            migrate_json_checkpoint(&checkpoint),
+            Err(CheckpointError::UnsupportedVersion(999))
+        ));
+    }
+}
@@ -0,0 +1,706 @@
+pub mod atomic;
+pub mod binary;
+pub mod delta;
+pub mod migration;
+
+use std::{
+    collections::HashSet,
+    fs::File,
+    io::{BufReader, Read},
+    panic::resume_unwind,
+    path::Path,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use alloy::{
+    eips::BlockNumberOrTag,
+    network::{primitives::HeaderResponse, BlockResponse, Network},
+    primitives::{Address, B256},
+    providers::Provider,
+};
+use atomic::DEFAULT_CHECKPOINT_ROTATIONS;
+use binary::{
+    is_binary_checkpoint, read_binary_checkpoint, read_binary_checkpoint_header,
+    write_binary_checkpoint, CheckpointCompression, CheckpointHeader,
+};
+use delta::{CheckpointDelta, DEFAULT_MAX_CHECKPOINT_DELTAS};
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+
+use super::amms_are_congruent;
+use crate::{
+    amm::{
+        erc_4626,
+        factory::{AutomatedMarketMakerFactory, Factory},
+        uniswap_v2::factory::UniswapV2Factory,
+        uniswap_v3::factory::UniswapV3Factory,
+        AutomatedMarketMaker, AMM,
+    },
+    discovery,
+    errors::{AMMError, CheckpointError},
+    filters,
+    state_space::cache::cache_depth_for_chain,
+};
+
+/// The schema version of checkpoints written by this crate, see `migration` for the schema history
+pub const CHECKPOINT_VERSION: u16 = 1;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Schema version the checkpoint was written with, older versions are migrated to `CHECKPOINT_VERSION` on load
+    pub version: u16,
+    pub timestamp: usize,
+    pub block_number: u64,
+    pub factories: Vec<Factory>,
+    pub amms: Vec<AMM>,
+}
+
+impl Checkpoint {
+    pub fn new(
+        timestamp: usize,
+        block_number: u64,
+        factories: Vec<Factory>,
+        amms: Vec<AMM>,
+    ) -> Checkpoint {
+        Checkpoint {
+            version: CHECKPOINT_VERSION,
+            timestamp,
+            block_number,
+            factories,
+            amms,
+        }
+    }
+}
+
+// Get all pairs from last synced block and sync reserve values for each Dex in the `dexes` vec.
+// Binary checkpoints are rejected with `CheckpointError::ChainIdMismatch` if they were synced on a different chain than the provider,
+// and with `CheckpointError::NonCanonicalTicks` if their block was reorged out and they contain Uniswap V3 pools, which then need a full sync.
+// JSON checkpoints are updated with a delta of the AMMs changed since the checkpoint block, see `construct_checkpoint_delta`.
+// New ERC4626 vaults are only discovered if the checkpoint already contains vaults, since vaults are not created by a tracked factory.
+pub async fn sync_amms_from_checkpoint<N, P, A>(
+    path_to_checkpoint: A,
+    step: u64,
+    provider: Arc<P>,
+) -> Result<(Vec<Factory>, Vec<AMM>), AMMError>
+where
+    N: Network,
+    P: Provider<N> + 'static,
+    A: AsRef<Path>,
+{
+    let current_block = provider.get_block_number().await?;
+
+    // Binary checkpoints are rewritten in the binary format with the same compression, while JSON checkpoints are
+    // updated with a delta unless the checkpoint at the path could not be read with all of its deltas
+    let (binary_header, checkpoint, is_delta_base) =
+        read_checkpoint_with_deltas(&path_to_checkpoint)?;
+    let delta_base = is_delta_base.then(|| checkpoint.clone());
+
+    // New AMMs are discovered from the checkpoint block, unless the checkpoint block was reorged out
+    let mut from_block = checkpoint.block_number;
+
+    // Binary checkpoints record the chain and block they were synced at, which are verified against the provider
+    let chain_id = match &binary_header {
+        Some(header) => {
+            let chain_id = provider.get_chain_id().await?;
+            if header.chain_id != chain_id {
+                return Err(CheckpointError::ChainIdMismatch(header.chain_id, chain_id).into());
+            }
+
+            let block_hash = provider
+                .get_block_by_number(BlockNumberOrTag::Number(header.block_number))
+                .await?
+                .map(|block| block.header().hash());
+
+            if block_hash != Some(header.block_hash) {
+                // Ticks are only synced forward, so ticks changed in the blocks that were reorged out can not be unwound
+                if checkpoint
+                    .amms
+                    .iter()
+                    .any(|amm| matches!(amm, AMM::UniswapV3Pool(_)))
+                {
+                    return Err(CheckpointError::NonCanonicalTicks(header.block_number).into());
+                }
+
+                // AMMs created in the blocks that replaced the checkpoint block would be missed, so discover AMMs from before the reorg
+                from_block = header
+                    .block_number
+                    .saturating_sub(cache_depth_for_chain(chain_id) as u64);
+
+                tracing::warn!(
+                    checkpoint_block = header.block_number,
+                    checkpoint_block_hash = ?header.block_hash,
+                    ?block_hash,
+                    from_block,
+                    "checkpoint block is no longer canonical, discovering new amms from an earlier block"
+                );
+            }
+
+            Some(chain_id)
+        }
+        None => None,
+    };
+
+    // Sort all of the pools from the checkpoint into uniswap_v2_pools, uniswap_v3_pools and erc_4626_vaults so we can sync them concurrently
+    let (uniswap_v2_pools, uniswap_v3_pools, erc_4626_vaults) = sort_amms(checkpoint.amms);
+
+    let mut aggregated_amms = vec![];
+    let mut handles = vec![];
+
+    // Sync all uniswap v2 pools from checkpoint
+    if !uniswap_v2_pools.is_empty() {
+        handles.push(
+            batch_sync_amms_from_checkpoint(
+                uniswap_v2_pools,
+                Some(current_block),
+                provider.clone(),
+            )
+            .await,
+        );
+    }
+
+    // Sync all uniswap v3 pools from checkpoint
+    if !uniswap_v3_pools.is_empty() {
+        handles.push(
+            batch_sync_amms_from_checkpoint(
+                uniswap_v3_pools,
+                Some(current_block),
+                provider.clone(),
+            )
+            .await,
+        );
+    }
+
+    // Sync all erc4626 vaults from checkpoint and discover vaults since the checkpoint block.
+    // Discovery scans the deposit and withdraw logs of every contract, so it only runs for checkpoints that track vaults.
+    if !erc_4626_vaults.is_empty() {
+        let known_vaults = erc_4626_vaults
+            .iter()
+            .map(|amm| amm.address())
+            .collect::<HashSet<Address>>();
+
+        handles.push(
+            batch_sync_amms_from_checkpoint(erc_4626_vaults, Some(current_block), provider.clone())
+                .await,
+        );
+
+        handles.push(get_new_vaults_from_range(
+            known_vaults,
+            from_block + 1,
+            current_block,
+            step,
+            provider.clone(),
+        ));
+    }
+
+    // Sync all pools from the since synced block
+    handles.extend(
+        get_new_amms_from_range(
+            checkpoint.factories.clone(),
+            from_block,
+            current_block,
+            step,
+            provider.clone(),
+        )
+        .await,
+    );
+
+    for handle in handles {
+        match handle.await {
+            Ok(sync_result) => aggregated_amms.extend(sync_result?),
+            Err(err) => {
+                {
+                    if err.is_panic() {
+                        // Resume the panic on the main task
+                        resume_unwind(err.into_panic());
+                    }
+                }
+            }
+        }
+    }
+
+    // AMMs rediscovered from before the checkpoint block are already synced from the checkpoint
+    let mut synced_addresses = HashSet::new();
+    aggregated_amms.retain(|amm| synced_addresses.insert(amm.address()));
+
+    //update the sync checkpoint
+    match (binary_header, chain_id) {
+        (Some(header), Some(chain_id)) => {
+            let block_hash = provider
+                .get_block_by_number(BlockNumberOrTag::Number(current_block))
+                .await?
+                .ok_or(AMMError::BlockNumberNotFound)?
+                .header()
+                .hash();
+
+            construct_binary_checkpoint(
+                checkpoint.factories.clone(),
+                &aggregated_amms,
+                chain_id,
+                current_block,
+                block_hash,
+                rewrite_compression(header.compression),
+                path_to_checkpoint,
+            )?;
+        }
+        _ => match delta_base {
+            Some(mut prev_checkpoint) => construct_checkpoint_delta(
+                &mut prev_checkpoint,
+                checkpoint.factories.clone(),
+                &aggregated_amms,
+                current_block,
+                path_to_checkpoint,
+            )?,
+            None => construct_checkpoint(
+                checkpoint.factories.clone(),
+                &aggregated_amms,
+                current_block,
+                path_to_checkpoint,
+            )?,
+        },
+    }
+
+    Ok((checkpoint.factories, aggregated_amms))
+}
+
+pub async fn get_new_amms_from_range<N, P>(
+    factories: Vec<Factory>,
+    from_block: u64,
+    to_block: u64,
+    step: u64,
+    provider: Arc<P>,
+) -> Vec<JoinHandle<Result<Vec<AMM>, AMMError>>>
+where
+    N: Network,
+    P: Provider<N> + 'static,
+{
+    // Create the filter with all the pair created events
+    // Aggregate the populated pools from each thread
+    let mut handles = vec![];
+
+    for factory in factories.into_iter() {
+        let provider = provider.clone();
+
+        // Spawn a new thread to get all pools and sync data for each dex
+        handles.push(tokio::spawn(async move {
+            let mut amms = factory
+                .get_all_pools_from_logs(from_block, to_block, step, provider.clone())
+                .await?;
+
+            factory
+                .populate_amm_data(&mut amms, Some(to_block), provider.clone())
+                .await?;
+
+            // Clean empty pools
+            amms = filters::filter_empty_amms(amms);
+
+            Ok::<_, AMMError>(amms)
+        }));
+    }
+
+    handles
+}
+
+/// Discovers ERC4626 vaults between `from_block` and `to_block` inclusive, populated as of `to_block` and skipping vaults that are already known
+pub fn get_new_vaults_from_range<N, P>(
+    known_vaults: HashSet<Address>,
+    from_block: u64,
+    to_block: u64,
+    step: u64,
+    provider: Arc<P>,
+) -> JoinHandle<Result<Vec<AMM>, AMMError>>
+where
+    N: Network,
+    P: Provider<N> + 'static,
+{
+    tokio::spawn(async move {
+        if from_block > to_block {
+            return Ok(vec![]);
+        }
+
+        let vaults = discovery::erc_4626::discover_erc_4626_vaults_in_range(
+            from_block, to_block, step, provider,
+        )
+        .await?
+        .into_iter()
+        .filter(|vault| !known_vaults.contains(&vault.address()))
+        .map(AMM::ERC4626Vault)
+        .collect::<Vec<AMM>>();
+
+        // Clean empty vaults
+        Ok::<_, AMMError>(filters::filter_empty_amms(vaults))
+    })
+}
+
+pub async fn batch_sync_amms_from_checkpoint<N, P>(
+    mut amms: Vec<AMM>,
+    block_number: Option<u64>,
+    provider: Arc<P>,
+) -> JoinHandle<Result<Vec<AMM>, AMMError>>
+where
+    N: Network,
+    P: Provider<N> + 'static,
+{
+    let factory = match amms[0] {
+        AMM::UniswapV2Pool(_) => Some(Factory::UniswapV2Factory(UniswapV2Factory::new(
+            Address::ZERO,
+            0,
+            0,
+        ))),
+
+        AMM::UniswapV3Pool(_) => Some(Factory::UniswapV3Factory(UniswapV3Factory::new(
+            Address::ZERO,
+            0,
+        ))),
+
+        AMM::ERC4626Vault(_) => None,
+    };
+
+    // Spawn a new thread to get all pools and sync data for each dex
+    tokio::spawn(async move {
+        if !amms_are_congruent(&amms) {
+            return Err(AMMError::IncongruentAMMs);
+        }
+
+        match factory {
+            // Get all pool data via batched calls
+            Some(factory) => {
+                factory
+                    .populate_amm_data(&mut amms, block_number, provider)
+                    .await?;
+            }
+            // Vaults are not created by a factory, so get all vault data via batched calls directly
+            None => {
+                // Max batch size for call
+                let step = 50;
+                for amm_chunk in amms.chunks_mut(step) {
+                    erc_4626::batch_request::get_amm_data_batch_request(
+                        amm_chunk,
+                        block_number,
+                        provider.clone(),
+                    )
+                    .await?;
+                }
+            }
+        }
+
+        // Clean empty pools
+        amms = filters::filter_empty_amms(amms);
+
+        Ok::<_, AMMError>(amms)
+    })
+}
+
+pub fn sort_amms(amms: Vec<AMM>) -> (Vec<AMM>, Vec<AMM>, Vec<AMM>) {
+    let mut uniswap_v2_pools = vec![];
+    let mut uniswap_v3_pools = vec![];
+    let mut erc_4626_vaults = vec![];
+    for amm in amms {
+        match amm {
+            AMM::UniswapV2Pool(_) => uniswap_v2_pools.push(amm),
+            AMM::UniswapV3Pool(_) => uniswap_v3_pools.push(amm),
+            AMM::ERC4626Vault(_) => erc_4626_vaults.push(amm),
+        }
+    }
+
+    (uniswap_v2_pools, uniswap_v3_pools, erc_4626_vaults)
+}
+
+pub async fn get_new_pools_from_range<N, P>(
+    factories: Vec<Factory>,
+    from_block: u64,
+    to_block: u64,
+    step: u64,
+    provider: Arc<P>,
+) -> Vec<JoinHandle<Result<Vec<AMM>, AMMError>>>
+where
+    N: Network,
+    P: Provider<N> + 'static,
+{
+    // Create the filter with all the pair created events
+    // Aggregate the populated pools from each thread
+    let mut handles = vec![];
+
+    for factory in factories {
+        let provider = provider.clone();
+
+        // Spawn a new thread to get all pools and sync data for each dex
+        handles.push(tokio::spawn(async move {
+            let mut pools = factory
+                .get_all_pools_from_logs(from_block, to_block, step, provider.clone())
+                .await?;
+
+            factory
+                .populate_amm_data(&mut pools, Some(to_block), provider.clone())
+                .await?;
+
+            // Clean empty pools
+            pools = filters::filter_empty_amms(pools);
+
+            Ok::<_, AMMError>(pools)
+        }));
+    }
+
+    handles
+}
+
+/// Atomically writes a full JSON checkpoint, removing any deltas to the previous checkpoint at the path.
+///
+/// Keeps `DEFAULT_CHECKPOINT_ROTATIONS` previous checkpoints. See `construct_checkpoint_delta` to only write the AMMs
+/// that changed since the previous checkpoint.
+pub fn construct_checkpoint<P>(
+    factories: Vec<Factory>,
+    amms: &[AMM],
+    latest_block: u64,
+    checkpoint_path: P,
+) -> Result<(), CheckpointError>
+where
+    P: AsRef<Path>,
+{
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs_f64() as usize;
+    let checkpoint = Checkpoint::new(timestamp, latest_block, factories, amms.to_vec());
+
+    write_full_checkpoint(&checkpoint, checkpoint_path.as_ref())
+}
+
+/// Writes the AMMs as a delta to the JSON checkpoint at the path, advancing `prev_checkpoint` to `latest_block`.
+///
+/// `prev_checkpoint` must be the checkpoint at the path with its deltas applied, e.g. as returned by `read_checkpoint`
+/// or advanced by a previous call, so the checkpoint is never read again between deltas. A delta only contains the AMMs
+/// added, modified or removed since the previous checkpoint block, and the deltas are compacted into a full checkpoint
+/// once there are more than `DEFAULT_MAX_CHECKPOINT_DELTAS`. If `prev_checkpoint` is not older than `latest_block`,
+/// a full checkpoint is written instead.
+pub fn construct_checkpoint_delta<P>(
+    prev_checkpoint: &mut Checkpoint,
+    factories: Vec<Factory>,
+    amms: &[AMM],
+    latest_block: u64,
+    checkpoint_path: P,
+) -> Result<(), CheckpointError>
+where
+    P: AsRef<Path>,
+{
+    let checkpoint_path = checkpoint_path.as_ref();
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs_f64() as usize;
+
+    if prev_checkpoint.block_number >= latest_block {
+        *prev_checkpoint = Checkpoint::new(timestamp, latest_block, factories, amms.to_vec());
+        return write_full_checkpoint(prev_checkpoint, checkpoint_path);
+    }
+
+    let delta = CheckpointDelta::between(prev_checkpoint, timestamp, latest_block, factories, amms);
+    delta::write_delta(checkpoint_path, &delta)?;
+    delta.apply(prev_checkpoint)?;
+
+    if delta::delta_files(checkpoint_path)?.len() > DEFAULT_MAX_CHECKPOINT_DELTAS {
+        // Deltas at or before the checkpoint block are ignored, so the checkpoint stays valid if removing them fails
+        write_json_checkpoint(prev_checkpoint, checkpoint_path)?;
+        delta::remove_deltas(checkpoint_path)?;
+    }
+
+    Ok(())
+}
+
+fn write_full_checkpoint(
+    checkpoint: &Checkpoint,
+    checkpoint_path: &Path,
+) -> Result<(), CheckpointError> {
+    // Remove the deltas first, so that deltas after the new checkpoint block are never applied to it
+    delta::remove_deltas(checkpoint_path)?;
+    write_json_checkpoint(checkpoint, checkpoint_path)
+}
+
+fn write_json_checkpoint(
+    checkpoint: &Checkpoint,
+    checkpoint_path: &Path,
+) -> Result<(), CheckpointError> {
+    atomic::write_atomic(checkpoint_path, DEFAULT_CHECKPOINT_ROTATIONS, |writer| {
+        Ok(serde_json::to_writer_pretty(writer, checkpoint)?)
+    })
+}
+
+/// Merges the deltas to the JSON checkpoint at the path into the full checkpoint and removes the deltas
+pub fn compact_checkpoint<P>(checkpoint_path: P) -> Result<(), CheckpointError>
+where
+    P: AsRef<Path>,
+{
+    let checkpoint_path = checkpoint_path.as_ref();
+
+    // Deltas are only written for JSON checkpoints, so any deltas to a binary checkpoint are stale
+    let (header, mut checkpoint) = read_checkpoint_file(checkpoint_path)?;
+    if header.is_none() {
+        delta::apply_deltas(checkpoint_path, &mut checkpoint)?;
+        write_json_checkpoint(&checkpoint, checkpoint_path)?;
+    }
+
+    // Deltas at or before the checkpoint block are ignored, so the checkpoint stays valid if this fails
+    delta::remove_deltas(checkpoint_path)
+}
+
+/// Atomically writes a binary checkpoint, keeping `DEFAULT_CHECKPOINT_ROTATIONS` previous checkpoints.
+///
+/// See `binary::CheckpointHeader` for the layout.
+pub fn construct_binary_checkpoint<P>(
+    factories: Vec<Factory>,
+    amms: &[AMM],
+    chain_id: u64,
+    latest_block: u64,
+    block_hash: B256,
+    compression: CheckpointCompression,
+    checkpoint_path: P,
+) -> Result<(), CheckpointError>
+where
+    P: AsRef<Path>,
+{
+    let header = CheckpointHeader::new(
+        chain_id,
+        latest_block,
+        block_hash,
+        SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+        compression,
+    );
+
+    // Deltas are only written for JSON checkpoints
+    delta::remove_deltas(&checkpoint_path)?;
+
+    atomic::write_atomic(checkpoint_path, DEFAULT_CHECKPOINT_ROTATIONS, |writer| {
+        write_binary_checkpoint(writer, &header, &factories, amms)
+    })
+}
+
+/// Reads the latest valid checkpoint, see `read_latest_valid_checkpoint`
+pub fn read_checkpoint<P>(checkpoint_path: P) -> Result<Checkpoint, CheckpointError>
+where
+    P: AsRef<Path>,
+{
+    let (_, checkpoint) = read_latest_valid_checkpoint(checkpoint_path)?;
+    Ok(checkpoint)
+}
+
+/// Reads the checkpoint at the path and applies its valid deltas, falling back to the most recent rotation that can be
+/// read if the checkpoint cannot be read. Returns the header for binary checkpoints.
+///
+/// Returns the error from reading the checkpoint at the path if no rotation can be read either.
+pub fn read_latest_valid_checkpoint<P>(
+    checkpoint_path: P,
+) -> Result<(Option<CheckpointHeader>, Checkpoint), CheckpointError>
+where
+    P: AsRef<Path>,
+{
+    let (header, checkpoint, _) = read_checkpoint_with_deltas(checkpoint_path)?;
+    Ok((header, checkpoint))
+}
+
+/// Reads the latest valid checkpoint, see `read_latest_valid_checkpoint`.
+///
+/// Also returns whether the checkpoint can be the base of a new delta, which is the case if it was read from the path
+/// with all of its deltas applied.
+fn read_checkpoint_with_deltas<P>(
+    checkpoint_path: P,
+) -> Result<(Option<CheckpointHeader>, Checkpoint, bool), CheckpointError>
+where
+    P: AsRef<Path>,
+{
+    let checkpoint_path = checkpoint_path.as_ref();
+
+    let err = match read_checkpoint_file(checkpoint_path) {
+        Ok((header, mut checkpoint)) => {
+            delta::apply_valid_deltas(checkpoint_path, &mut checkpoint);
+
+            // A new delta would never be applied after a delta that could not be applied
+            let is_delta_base = header.is_none()
+                && delta::delta_files(checkpoint_path).is_ok_and(|delta_files| {
+                    delta_files
+                        .iter()
+                        .all(|(block_number, _)| *block_number <= checkpoint.block_number)
+                });
+
+            return Ok((header, checkpoint, is_delta_base));
+        }
+        Err(err) => err,
+    };
+
+    for rotation_path in atomic::rotation_files(checkpoint_path) {
+        match read_checkpoint_file(&rotation_path) {
+            Ok((header, checkpoint)) => {
+                tracing::warn!(
+                    ?checkpoint_path,
+                    ?rotation_path,
+                    ?err,
+                    "failed to read checkpoint, falling back to previous checkpoint"
+                );
+                return Ok((header, checkpoint, false));
+            }
+            Err(rotation_err) => {
+                tracing::warn!(
+                    ?rotation_path,
+                    ?rotation_err,
+                    "failed to read previous checkpoint"
+                )
+            }
+        }
+    }
+
+    Err(err)
+}
+
+/// Reads a JSON or binary checkpoint without its deltas, detecting the format from the start of the file and migrating
+/// older schema versions
+pub fn read_base_checkpoint<P>(checkpoint_path: P) -> Result<Checkpoint, CheckpointError>
+where
+    P: AsRef<Path>,
+{
+    let (_, checkpoint) = read_checkpoint_file(checkpoint_path)?;
+    Ok(checkpoint)
+}
+
+fn read_checkpoint_file<P>(
+    checkpoint_path: P,
+) -> Result<(Option<CheckpointHeader>, Checkpoint), CheckpointError>
+where
+    P: AsRef<Path>,
+{
+    let mut reader = BufReader::new(File::open(checkpoint_path)?);
+
+    if is_binary_checkpoint(&mut reader)? {
+        let (header, checkpoint) = read_binary_checkpoint(reader)?;
+        return Ok((Some(header), checkpoint));
+    }
+
+    let mut checkpoint = String::new();
+    reader.read_to_string(&mut checkpoint)?;
+    Ok((None, migration::migrate_json_checkpoint(&checkpoint)?))
+}
+
+/// Reads the header of a binary checkpoint without decoding the AMMs, returning `None` for JSON checkpoints
+pub fn read_checkpoint_header<P>(
+    checkpoint_path: P,
+) -> Result<Option<CheckpointHeader>, CheckpointError>
+where
+    P: AsRef<Path>,
+{
+    let mut reader = BufReader::new(File::open(checkpoint_path)?);
+
+    if !is_binary_checkpoint(&mut reader)? {
+        return Ok(None);
+    }
+
+    Ok(Some(read_binary_checkpoint_header(reader)?))
+}
+
+/// The compression level is not stored in the checkpoint, so zstd checkpoints are rewritten at the default level
+fn rewrite_compression(compression: CheckpointCompression) -> CheckpointCompression {
+    match compression {
+        CheckpointCompression::None => CheckpointCompression::None,
+        CheckpointCompression::Zstd { .. } => CheckpointCompression::default_zstd(),
+    }
+}
+
+// Deconstructs the checkpoint into a Vec<AMM>
+pub fn deconstruct_checkpoint<P>(checkpoint_path: P) -> Result<(Vec<AMM>, u64), CheckpointError>
+where
+    P: AsRef<Path>,
+{
+    let checkpoint = read_checkpoint(checkpoint_path)?;
+    Ok((checkpoint.amms, checkpoint.block_number))
+}
@@ -1,15 +1,30 @@
 pub mod checkpoint;
 
-use std::{panic::resume_unwind, sync::Arc};
+use std::{
+    collections::{BTreeMap, HashMap},
+    panic::resume_unwind,
+    sync::Arc,
+};
 
-use alloy::{network::Network, providers::Provider};
+use alloy::{
+    network::Network,
+    primitives::Address,
+    providers::Provider,
+    rpc::types::eth::{Filter, Log},
+    sol_types::SolEvent,
+};
+use futures::{stream::FuturesOrdered, StreamExt};
 
 use crate::{
     amm::{
+        consts::POPULATE_TICK_DATA_STEP,
+        erc_4626,
         factory::{AutomatedMarketMakerFactory, Factory},
-        uniswap_v2, uniswap_v3, AutomatedMarketMaker, AMM,
+        uniswap_v2,
+        uniswap_v3::{self, IUniswapV3Pool, UniswapV3Pool},
+        AMM,
     },
-    errors::AMMError,
+    errors::{AMMError, EventLogError},
     filters,
 };
 
@@ -127,6 +142,7 @@ where
                 for amm_chunk in amms.chunks_mut(step) {
                     uniswap_v2::batch_request::get_amm_data_batch_request(
                         amm_chunk,
+                        Some(block_number),
                         provider.clone(),
                     )
                     .await?;
@@ -146,10 +162,16 @@ where
                 }
             }
 
-            // TODO: Implement batch request
             AMM::ERC4626Vault(_) => {
-                for amm in amms {
-                    amm.populate_data(None, provider.clone()).await?;
+                // Max batch size for call
+                let step = 50;
+                for amm_chunk in amms.chunks_mut(step) {
+                    erc_4626::batch_request::get_amm_data_batch_request(
+                        amm_chunk,
+                        Some(block_number),
+                        provider.clone(),
+                    )
+                    .await?;
                 }
             }
         }
@@ -160,3 +182,74 @@ where
     // For each pair in the pairs vec, get the pool data
     Ok(())
 }
+
+/// Rebuilds the tick bitmap and ticks of the Uniswap V3 pools in `amms` from their mint and burn logs between `from_block` and `to_block`.
+///
+/// Only the ticks are rebuilt, so the liquidity, price and tick of each pool are kept as populated.
+/// `from_block` must be at or before the creation block of each pool. AMMs other than Uniswap V3 pools are left untouched.
+pub async fn populate_tick_data<N, P>(
+    amms: &mut [AMM],
+    mut from_block: u64,
+    to_block: u64,
+    provider: Arc<P>,
+) -> Result<(), AMMError>
+where
+    N: Network,
+    P: Provider<N>,
+{
+    let mut pools: HashMap<Address, &mut UniswapV3Pool> = amms
+        .iter_mut()
+        .filter_map(|amm| match amm {
+            AMM::UniswapV3Pool(pool) => Some((pool.address, pool)),
+            _ => None,
+        })
+        .collect();
+
+    if pools.is_empty() {
+        return Ok(());
+    }
+
+    for pool in pools.values_mut() {
+        pool.tick_bitmap.clear();
+        pool.ticks.clear();
+    }
+
+    let pool_addresses = pools.keys().copied().collect::<Vec<Address>>();
+    let mut futures = FuturesOrdered::new();
+
+    while from_block <= to_block {
+        let provider = provider.clone();
+        let target_block = (from_block + POPULATE_TICK_DATA_STEP - 1).min(to_block);
+        let filter = Filter::new()
+            .event_signature(vec![
+                IUniswapV3Pool::Burn::SIGNATURE_HASH,
+                IUniswapV3Pool::Mint::SIGNATURE_HASH,
+            ])
+            .address(pool_addresses.clone())
+            .from_block(from_block)
+            .to_block(target_block);
+
+        futures.push_back(async move { provider.get_logs(&filter).await });
+
+        from_block += POPULATE_TICK_DATA_STEP;
+    }
+
+    let mut ordered_logs: BTreeMap<u64, Vec<Log>> = BTreeMap::new();
+    while let Some(result) = futures.next().await {
+        for log in result? {
+            let log_block_number = log
+                .block_number
+                .ok_or(EventLogError::LogBlockNumberNotFound)?;
+
+            ordered_logs.entry(log_block_number).or_default().push(log);
+        }
+    }
+
+    for log in ordered_logs.into_values().flatten() {
+        if let Some(pool) = pools.get_mut(&log.address()) {
+            pool.sync_ticks_from_log(log)?;
+        }
+    }
+
+    Ok(())
+}